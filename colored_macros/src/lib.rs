@@ -0,0 +1,163 @@
+//! Markup macros for [`colored`](https://docs.rs/colored).
+//!
+//! Don't depend on this crate directly, enable the `macros` feature of
+//! `colored` and use the macros it re-exports.
+//!
+//! The format string of the macros takes color tags, parsed at compile time.
+//! A tag is a `colored::theme::StyleSpec` where underscores can stand for
+//! spaces, the same as for `colored::markup::parse`:
+//!
+//! - colors: `<red>`, `<bright_blue>`, `<#ff8000>`...
+//! - background colors: `<on_red>`, `<on_bright_blue>`, `<on_#ff8000>`...
+//! - styles: `<bold>`, `<dimmed>`, `<italic>`, `<underline>`, `<blink>`,
+//!   `<reversed>`, `<hidden>` and `<strikethrough>`.
+//! - any mix of them: `<bold red on black>`, `<underline_cyan>`...
+//!
+//! Each tag is closed with `</name>`, in the order they were opened. Write
+//! `<<` for a literal `<`. The rest of the string is a regular format string,
+//! so `{}` placeholders and their arguments work as with `format!`, colored
+//! arguments included.
+//!
+//! The colors are only written if `colored` would write them for a
+//! `ColoredString`, according to `colored::control::SHOULD_COLORIZE`: for
+//! stdout with `cformat!`, `cprint!` and `cprintln!`, and for stderr with
+//! `ceprint!` and `ceprintln!`.
+//!
+//! Unknown or unbalanced tags don't compile:
+//!
+//! ```compile_fail
+//! # #[macro_use] extern crate colored;
+//! cformat!("<rouge>error</rouge>");
+//! ```
+//!
+//! ```compile_fail
+//! # #[macro_use] extern crate colored;
+//! cformat!("<red><bold>error</red></bold>");
+//! ```
+//!
+//! ```compile_fail
+//! # #[macro_use] extern crate colored;
+//! cformat!("<red>error");
+//! ```
+
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate quote;
+extern crate syn;
+
+mod markup;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse::Parser, Ident, LitStr};
+
+/// Expand to `std::$mac!(...)` called with the colored format string if
+/// colors should be written to the stream `$mac` writes to, or the plain one
+/// otherwise.
+fn expand(input: TokenStream2, mac: &str) -> TokenStream2 {
+    let parser = |input: syn::parse::ParseStream| {
+        if input.is_empty() {
+            return Ok(None);
+        }
+        let template: LitStr = input.parse()?;
+        let args: TokenStream2 = input.parse()?;
+        Ok(Some((template, args)))
+    };
+
+    let stream = if mac.starts_with('e') {
+        quote!(Stderr)
+    } else {
+        quote!(Stdout)
+    };
+    let mac = Ident::new(mac, Span::call_site());
+
+    let (template, args) = match parser.parse2(input) {
+        Ok(Some(parsed)) => parsed,
+        Ok(None) => return quote!(::std::#mac!()),
+        Err(err) => return err.to_compile_error(),
+    };
+
+    let rendered = match markup::render(&template.value()) {
+        Ok(rendered) => rendered,
+        Err(msg) => return syn::Error::new(template.span(), msg).to_compile_error(),
+    };
+    // keep the span, so that the placeholders can capture variables
+    let colored = LitStr::new(&rendered.colored, template.span());
+    let plain = LitStr::new(&rendered.plain, template.span());
+
+    quote!(
+        if ::colored::__private::should_colorize_for(::colored::control::Stream::#stream) {
+            ::std::#mac!(#colored #args)
+        } else {
+            ::std::#mac!(#plain #args)
+        }
+    )
+}
+
+/// Like `format!`, with color tags in the format string.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate colored;
+/// use colored::Colorize;
+///
+/// let msg = "file not found";
+/// let line = cformat!("<red><bold>error</bold></red>: {}", msg);
+/// assert_eq!(line, format!("{}: {}", "error".red().bold(), msg));
+/// ```
+#[proc_macro]
+pub fn cformat(input: TokenStream) -> TokenStream {
+    expand(input.into(), "format").into()
+}
+
+/// Like `print!`, with color tags in the format string.
+#[proc_macro]
+pub fn cprint(input: TokenStream) -> TokenStream {
+    expand(input.into(), "print").into()
+}
+
+/// Like `println!`, with color tags in the format string.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate colored;
+///
+/// let branch = "main";
+/// cprintln!("on branch <green>{branch}</green>");
+/// ```
+#[proc_macro]
+pub fn cprintln(input: TokenStream) -> TokenStream {
+    expand(input.into(), "println").into()
+}
+
+/// Like `eprint!`, with color tags in the format string.
+#[proc_macro]
+pub fn ceprint(input: TokenStream) -> TokenStream {
+    expand(input.into(), "eprint").into()
+}
+
+/// Like `eprintln!`, with color tags in the format string.
+#[proc_macro]
+pub fn ceprintln(input: TokenStream) -> TokenStream {
+    expand(input.into(), "eprintln").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decides_for(mac: &str) -> bool {
+        let expanded = expand(quote!("<red>x</red>"), mac).to_string();
+        expanded.contains("Stream :: Stderr")
+    }
+
+    #[test]
+    fn stream_of_the_decision() {
+        assert!(!decides_for("format"));
+        assert!(!decides_for("print"));
+        assert!(!decides_for("println"));
+        assert!(decides_for("eprint"));
+        assert!(decides_for("eprintln"));
+    }
+}
@@ -0,0 +1,334 @@
+//! Turn a format string with color tags into two format strings: one with the
+//! escape sequences and one without.
+
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// The styles, in the order and with the codes `colored` writes them.
+const STYLES: [(&str, &str); 8] = [
+    ("bold", "1"),
+    ("dimmed", "2"),
+    ("underline", "4"),
+    ("reversed", "7"),
+    ("italic", "3"),
+    ("blink", "5"),
+    ("hidden", "8"),
+    ("strikethrough", "9"),
+];
+
+/// The other names `colored` accepts for a style, `None` for the ones which
+/// add nothing.
+const STYLE_ALIASES: [(&str, Option<&str>); 3] = [
+    ("reverse", Some("reversed")),
+    ("clear", None),
+    ("normal", None),
+];
+
+/// The format string rendered with and without colors.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rendered {
+    pub colored: String,
+    pub plain: String,
+}
+
+#[derive(Clone, Default, PartialEq, Eq)]
+struct Attributes {
+    fg: Option<String>,
+    bg: Option<String>,
+    styles: [bool; 8],
+}
+
+impl Attributes {
+    fn is_plain(&self) -> bool {
+        *self == Attributes::default()
+    }
+
+    /// The attributes of `tag` on top of these ones: its colors replace
+    /// these, its styles are added.
+    fn with(mut self, tag: &Attributes) -> Self {
+        if tag.fg.is_some() {
+            self.fg = tag.fg.clone();
+        }
+        if tag.bg.is_some() {
+            self.bg = tag.bg.clone();
+        }
+        for (on, &tag_on) in self.styles.iter_mut().zip(&tag.styles) {
+            *on |= tag_on;
+        }
+        self
+    }
+
+    fn write_to(&self, out: &mut String) {
+        let mut params: Vec<&str> = STYLES
+            .iter()
+            .zip(&self.styles)
+            .filter(|&(_, &on)| on)
+            .map(|(&(_, code), _)| code)
+            .collect();
+        params.extend(self.bg.as_deref());
+        params.extend(self.fg.as_deref());
+
+        out.push_str("\x1B[");
+        out.push_str(&params.join(";"));
+        out.push('m');
+    }
+}
+
+fn parse_color(name: &str, background: bool) -> Option<String> {
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        let (r, g, b) = (channel(0)?, channel(2)?, channel(4)?);
+        let kind = if background { 48 } else { 38 };
+        return Some(format!("{};2;{};{};{}", kind, r, g, b));
+    }
+
+    let (name, offset) = match name.strip_prefix("bright ") {
+        Some(name) => (name, 60),
+        None => (name, 0),
+    };
+    let name = if name == "purple" { "magenta" } else { name };
+    let idx = COLORS.iter().position(|&color| color == name)?;
+    let base = if background { 40 } else { 30 };
+    Some((base + offset + idx).to_string())
+}
+
+/// Whether `name` is a style, and which one if it adds any.
+fn parse_style(name: &str) -> Option<Option<usize>> {
+    let name = match STYLE_ALIASES.iter().find(|&&(alias, _)| alias == name) {
+        Some(&(_, Some(style))) => style,
+        Some(&(_, None)) => return Some(None),
+        None => name,
+    };
+    STYLES
+        .iter()
+        .position(|&(style, _)| style == name)
+        .map(Some)
+}
+
+/// Parse a tag with the grammar of `colored::theme::StyleSpec`, where
+/// underscores stand for spaces: `bold`, `bright_blue`, `on_#ff8000` or
+/// `bold red on black`.
+fn parse_tag(tag: &str) -> Option<Attributes> {
+    let tag = tag.replace('_', " ").to_lowercase();
+    let mut words = tag.split_whitespace().peekable();
+    // an empty spec is valid, but not as a tag
+    words.peek()?;
+
+    let mut attributes = Attributes::default();
+    while let Some(word) = words.next() {
+        let background = word == "on";
+        let word = if background { words.next()? } else { word };
+        let word = match word {
+            "bright" => format!("bright {}", words.next()?),
+            word => word.to_string(),
+        };
+
+        if !background {
+            if let Some(style) = parse_style(&word) {
+                if let Some(idx) = style {
+                    attributes.styles[idx] = true;
+                }
+                continue;
+            }
+        }
+
+        let color = parse_color(&word, background)?;
+        if background {
+            attributes.bg = Some(color);
+        } else {
+            attributes.fg = Some(color);
+        }
+    }
+    Some(attributes)
+}
+
+/// Render the color tags of `template`, a format string such as
+/// `"<red><bold>error</bold></red>: {}"`.
+///
+/// Tags are specs as parsed by `colored::theme::StyleSpec`, such as `red`,
+/// `bright_blue`, `on_#ff8000` or `bold red on black`, and must be closed in
+/// order. `<<` is a literal `<`, and format placeholders are kept untouched.
+///
+/// The arguments may be colored strings whose final reset clears the colors
+/// of the tags around them, so the colors are written again after each
+/// placeholder.
+pub fn render(template: &str) -> Result<Rendered, String> {
+    let mut rendered = Rendered {
+        colored: String::with_capacity(template.len()),
+        plain: String::with_capacity(template.len()),
+    };
+    // the open tags, with their position and the attributes inside them
+    let mut open: Vec<(&str, usize, Attributes)> = Vec::new();
+    let mut applied = Attributes::default();
+    // whether an argument may have reset the colors since they were applied
+    let mut reopen = false;
+
+    let mut rest = template;
+    while let Some(c) = rest.chars().next() {
+        let offset = template.len() - rest.len();
+        let len = match c {
+            '<' if rest.starts_with("<<") => 2,
+            '<' => {
+                let end = rest
+                    .find('>')
+                    .ok_or_else(|| format!("unclosed `<` at offset {}", offset))?;
+                let tag = &rest[1..end];
+                rest = &rest[end + 1..];
+
+                if let Some(name) = tag.strip_prefix('/') {
+                    match open.pop() {
+                        Some((open_name, _, _)) if open_name == name => (),
+                        Some((open_name, open_offset, _)) => {
+                            return Err(format!(
+                                "`</{}>` at offset {} doesn't close `<{}>` at offset {}",
+                                name, offset, open_name, open_offset
+                            ))
+                        }
+                        None => {
+                            return Err(format!(
+                                "`</{}>` at offset {} closes no tag",
+                                name, offset
+                            ))
+                        }
+                    }
+                } else {
+                    let parsed = parse_tag(tag)
+                        .ok_or_else(|| format!("unknown tag `<{}>` at offset {}", tag, offset))?;
+                    let inherited = open.last().map(|open| open.2.clone()).unwrap_or_default();
+                    open.push((tag, offset, inherited.with(&parsed)));
+                }
+                continue;
+            }
+            // placeholders may contain `<` as alignment, copy them as is
+            '{' if rest.starts_with("{{") => 2,
+            '{' => rest.find('}').map_or(rest.len(), |end| end + 1),
+            _ => c.len_utf8(),
+        };
+        let text = if c == '<' { "<" } else { &rest[..len] };
+        let placeholder = c == '{' && !rest.starts_with("{{");
+        rest = &rest[len..];
+
+        let wanted = open.last().map(|open| open.2.clone()).unwrap_or_default();
+        if wanted != applied {
+            if !applied.is_plain() {
+                rendered.colored.push_str("\x1B[0m");
+            }
+            if !wanted.is_plain() {
+                wanted.write_to(&mut rendered.colored);
+            }
+            applied = wanted;
+        } else if reopen && !applied.is_plain() {
+            applied.write_to(&mut rendered.colored);
+        }
+        reopen = placeholder;
+        rendered.colored.push_str(text);
+        rendered.plain.push_str(text);
+    }
+
+    if let Some((name, offset, _)) = open.pop() {
+        return Err(format!("unclosed tag `<{}>` at offset {}", name, offset));
+    }
+    if !applied.is_plain() {
+        rendered.colored.push_str("\x1B[0m");
+    }
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colored(template: &str) -> String {
+        render(template).unwrap().colored
+    }
+
+    #[test]
+    fn plain_text() {
+        let rendered = render("no tags: {} {name:>4}").unwrap();
+        assert_eq!("no tags: {} {name:>4}", rendered.colored);
+        assert_eq!(rendered.colored, rendered.plain);
+    }
+
+    #[test]
+    fn nested_tags() {
+        let rendered = render("<red><bold>error</bold></red>: {}").unwrap();
+        assert_eq!("\x1B[1;31merror\x1B[0m: {}", rendered.colored);
+        assert_eq!("error: {}", rendered.plain);
+        assert_eq!(
+            "\x1B[31ma\x1B[0m\x1B[1;31mb\x1B[0m\x1B[31mc\x1B[0m",
+            colored("<red>a<bold>b</bold>c</red>")
+        );
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!("\x1B[95mx\x1B[0m", colored("<bright_magenta>x</bright_magenta>"));
+        assert_eq!("\x1B[35mx\x1B[0m", colored("<purple>x</purple>"));
+        assert_eq!("\x1B[44;33mx\x1B[0m", colored("<yellow><on_blue>x</on_blue></yellow>"));
+        assert_eq!("\x1B[38;2;255;128;0mx\x1B[0m", colored("<#ff8000>x</#ff8000>"));
+        assert_eq!("\x1B[48;2;0;0;1mx\x1B[0m", colored("<on_#000001>x</on_#000001>"));
+    }
+
+    #[test]
+    fn spec_tags() {
+        assert_eq!("\x1B[1;40;31mx\x1B[0m", colored("<bold red on black>x</bold red on black>"));
+        assert_eq!(
+            "\x1B[4;100;35mx\x1B[0m",
+            colored("<underline_purple_on_bright_black>x</underline_purple_on_bright_black>")
+        );
+        assert_eq!("\x1B[7mx\x1B[0m", colored("<reverse>x</reverse>"));
+        assert_eq!("\x1B[94mx\x1B[0m", colored("<Bright_Blue>x</Bright_Blue>"));
+        assert_eq!("x", colored("<normal>x</normal>"));
+    }
+
+    #[test]
+    fn arguments_reopen_the_colors() {
+        assert_eq!("\x1B[31m{}\x1B[31m rest\x1B[0m", colored("<red>{} rest</red>"));
+        assert_eq!("\x1B[31m{:>4}\x1B[0m", colored("<red>{:>4}</red>"));
+        assert_eq!("\x1B[31m{{x\x1B[0m", colored("<red>{{x</red>"));
+        assert_eq!(
+            "\x1B[31m{}\x1B[0m\x1B[1;31mb\x1B[0m",
+            colored("<red>{}<bold>b</bold></red>")
+        );
+    }
+
+    #[test]
+    fn inner_tags_override_colors() {
+        assert_eq!("\x1B[32mx\x1B[0m", colored("<red><green>x</green></red>"));
+    }
+
+    #[test]
+    fn empty_tags_write_nothing() {
+        assert_eq!("ab", colored("a<red></red>b"));
+    }
+
+    #[test]
+    fn escaping() {
+        let rendered = render("a << b <red><<</red> {:<5} {{<<}}").unwrap();
+        assert_eq!("a < b < {:<5} {{<}}", rendered.plain);
+        assert_eq!("a < b \x1B[31m<\x1B[0m {:<5} {{<}}", rendered.colored);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Err("unknown tag `<rouge>` at offset 2".into()), render("a <rouge>b</rouge>"));
+        assert_eq!(Err("unknown tag `<#12>` at offset 0".into()), render("<#12>"));
+        assert_eq!(Err("unknown tag `<>` at offset 1".into()), render("a<></>"));
+        assert_eq!(Err("unknown tag `<on>` at offset 0".into()), render("<on>a</on>"));
+        assert_eq!(
+            Err("unknown tag `<on_bold>` at offset 0".into()),
+            render("<on_bold>a</on_bold>")
+        );
+        assert_eq!(Err("unclosed `<` at offset 1".into()), render("a<red"));
+        assert_eq!(Err("unclosed tag `<red>` at offset 0".into()), render("<red>a"));
+        assert_eq!(Err("`</red>` at offset 1 closes no tag".into()), render("a</red>"));
+        assert_eq!(
+            Err("`</red>` at offset 12 doesn't close `<bold>` at offset 5".into()),
+            render("<red><bold>a</red></bold>")
+        );
+    }
+}
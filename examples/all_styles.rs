@@ -1,6 +1,6 @@
 extern crate colored;
 
-use colored::{Color, Colorize, NO_STYLE, Style, Styles};
+use colored::{Color, Colorize, NO_STYLE, Styles};
 
 static STYLES: [(Styles, &str); 8] = [
     (Styles::Bold, "BLD"),
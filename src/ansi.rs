@@ -0,0 +1,531 @@
+//! Helpers for strings containing ANSI escape sequences, such as the output
+//! of a [`ColoredString`](crate::ColoredString).
+//!
+//! Widths are counted in terminal columns: escape sequences take none, and
+//! wide characters (CJK, most emoji) take two.
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A piece of a string which may contain ANSI escape sequences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Segment<'a> {
+    /// A complete escape sequence, e.g. `"\x1B[31m"`.
+    Escape(&'a str),
+    /// Text between escape sequences.
+    Text(&'a str),
+}
+
+/// Split `s` into escape sequences and the text between them.
+pub(crate) fn segments(s: &str) -> Segments<'_> {
+    Segments { rest: s }
+}
+
+pub(crate) struct Segments<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let (len, escape) = match escape_len(self.rest) {
+            Some(len) => (len, true),
+            // an ESC which doesn't start a complete sequence is kept as text
+            None if self.rest.starts_with('\x1B') => (1, false),
+            None => (self.rest.find('\x1B').unwrap_or(self.rest.len()), false),
+        };
+
+        let (segment, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Some(if escape {
+            Segment::Escape(segment)
+        } else {
+            Segment::Text(segment)
+        })
+    }
+}
+
+/// The length of the escape sequence at the start of `s`, if there is one.
+///
+/// Recognizes CSI sequences (`ESC [ ... final`), OSC sequences (`ESC ] ...`
+/// terminated by BEL or `ESC \`) and two bytes escapes (`ESC x`).
+fn escape_len(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    if bytes.first() != Some(&0x1B) {
+        return None;
+    }
+
+    match bytes.get(1) {
+        Some(b'[') => bytes[2..]
+            .iter()
+            .position(|b| (0x40..=0x7E).contains(b))
+            .map(|end| end + 3),
+        Some(b']') => {
+            let mut i = 2;
+            while i < bytes.len() {
+                match bytes[i] {
+                    0x07 => return Some(i + 1),
+                    0x1B if bytes.get(i + 1) == Some(&b'\\') => return Some(i + 2),
+                    _ => i += 1,
+                }
+            }
+            None
+        }
+        Some(b) if b.is_ascii() => Some(2),
+        _ => None,
+    }
+}
+
+/// The number of columns `s` takes on a terminal, ignoring escape sequences
+/// and counting wide characters (CJK, emoji) as two columns.
+///
+/// ```rust
+/// # use colored::*;
+/// assert_eq!(colored::ansi::visible_width(&"日本".red().to_string()), 4);
+/// ```
+pub fn visible_width(s: &str) -> usize {
+    segments(s)
+        .map(|segment| match segment {
+            Segment::Escape(_) => 0,
+            Segment::Text(text) => text.width(),
+        })
+        .sum()
+}
+
+/// Cut `s` so that it takes at most `width` columns, ending it with
+/// `ellipsis` if anything was cut.
+///
+/// Grapheme clusters are never split, and escape sequences are kept whole.
+/// Colors and hyperlinks still open at the cut are closed after the
+/// ellipsis, so the result can be printed on its own.
+///
+/// ```rust
+/// # use colored::*;
+/// use colored::ansi::truncate_visible;
+///
+/// let s = format!("{} world", "hello".green());
+/// assert_eq!(truncate_visible(&s, 20, "…"), s);
+/// assert_eq!(truncate_visible(&s, 8, "…"), format!("{} w…", "hello".green()));
+/// assert_eq!(truncate_visible(&s, 3, "…"), format!("{}", "he…".green()));
+/// ```
+pub fn truncate_visible<'a>(s: &'a str, width: usize, ellipsis: &str) -> Cow<'a, str> {
+    if visible_width(s) <= width {
+        return s.into();
+    }
+
+    let ellipsis = match visible_width(ellipsis) {
+        ellipsis_width if ellipsis_width <= width => Cow::from(ellipsis),
+        _ => truncate_visible(ellipsis, width, ""),
+    };
+    let mut budget = width - visible_width(&ellipsis);
+
+    let mut res = String::with_capacity(s.len());
+    let mut sgr_open = false;
+    let mut hyperlink_open = false;
+    'segments: for segment in segments(s) {
+        match segment {
+            Segment::Escape(escape) => {
+                if let Some(reset) = sgr_resets(escape) {
+                    sgr_open = !reset;
+                } else if let Some(uri) = hyperlink_uri(escape) {
+                    hyperlink_open = !uri.is_empty();
+                }
+                res.push_str(escape);
+            }
+            Segment::Text(text) => {
+                for grapheme in text.graphemes(true) {
+                    let grapheme_width = grapheme.width();
+                    if grapheme_width > budget {
+                        break 'segments;
+                    }
+                    budget -= grapheme_width;
+                    res.push_str(grapheme);
+                }
+            }
+        }
+    }
+
+    res.push_str(&ellipsis);
+    if hyperlink_open {
+        res.push_str("\x1B]8;;\x1B\\");
+    }
+    if sgr_open {
+        res.push_str("\x1B[0m");
+    }
+    res.into()
+}
+
+/// Wrap `s` on word boundaries so that no line takes more than `width`
+/// columns. Words longer than a line are split between grapheme clusters.
+///
+/// Colors and styles active at the end of a line are closed there and opened
+/// again at the start of the next one, so each line can be printed on its
+/// own. Newlines in `s` are kept, and spaces where lines are wrapped dropped.
+///
+/// ```rust
+/// # use colored::*;
+/// use colored::ansi::wrap;
+///
+/// let text = format!("some {} text", "very important".red());
+/// assert_eq!(
+///     wrap(&text, 14),
+///     vec![
+///         format!("some {}", "very".red()),
+///         format!("{} text", "important".red()),
+///     ]
+/// );
+/// ```
+pub fn wrap(s: &str, width: usize) -> Vec<String> {
+    let mut wrapper = Wrapper {
+        width,
+        lines: Vec::new(),
+        line: String::new(),
+        line_width: 0,
+        active: String::new(),
+    };
+
+    for paragraph in s.split('\n') {
+        let mut words = WordSplit::new(paragraph);
+        let mut space = words.next_space();
+        let mut first = true;
+        while let Some(word) = words.next_word() {
+            let word_width = visible_width(word);
+            // spaces are kept at the start of a paragraph, not where we wrap
+            let space_width = if first || wrapper.line_width > 0 {
+                visible_width(space)
+            } else {
+                0
+            };
+
+            if wrapper.line_width > 0 && wrapper.line_width + space_width + word_width > width {
+                wrapper.break_line();
+                wrapper.push_escapes(space);
+            } else if space_width > 0 {
+                wrapper.push_text(space);
+            } else {
+                wrapper.push_escapes(space);
+            }
+
+            wrapper.push_word(word, word_width);
+            space = words.next_space();
+            first = false;
+        }
+        wrapper.push_escapes(space);
+        wrapper.break_line();
+    }
+
+    wrapper.lines
+}
+
+struct Wrapper {
+    width: usize,
+    lines: Vec<String>,
+    line: String,
+    line_width: usize,
+    /// SGR sequences since the last reset, to open again on a new line.
+    active: String,
+}
+
+impl Wrapper {
+    fn break_line(&mut self) {
+        if !self.active.is_empty() {
+            self.line.push_str("\x1B[0m");
+        }
+        self.lines.push(core::mem::replace(&mut self.line, self.active.clone()));
+        self.line_width = 0;
+    }
+
+    fn push_escape(&mut self, escape: &str) {
+        match sgr_resets(escape) {
+            Some(true) => self.active.clear(),
+            Some(false) => self.active.push_str(escape),
+            None => (),
+        }
+        self.line.push_str(escape);
+    }
+
+    /// Push the escape sequences of something which isn't shown, e.g. spaces
+    /// dropped at the end of a paragraph.
+    fn push_escapes(&mut self, s: &str) {
+        for segment in segments(s) {
+            if let Segment::Escape(escape) = segment {
+                self.push_escape(escape);
+            }
+        }
+    }
+
+    fn push_text(&mut self, s: &str) {
+        for segment in segments(s) {
+            match segment {
+                Segment::Escape(escape) => self.push_escape(escape),
+                Segment::Text(text) => {
+                    self.line.push_str(text);
+                    self.line_width += text.width();
+                }
+            }
+        }
+    }
+
+    fn push_word(&mut self, word: &str, word_width: usize) {
+        for segment in segments(word) {
+            match segment {
+                Segment::Escape(escape) => self.push_escape(escape),
+                Segment::Text(text) if word_width <= self.width => {
+                    self.line.push_str(text);
+                    self.line_width += text.width();
+                }
+                Segment::Text(text) => {
+                    for grapheme in text.graphemes(true) {
+                        let grapheme_width = grapheme.width();
+                        if self.line_width > 0 && self.line_width + grapheme_width > self.width {
+                            self.break_line();
+                        }
+                        self.line.push_str(grapheme);
+                        self.line_width += grapheme_width;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Split a line in alternating runs of whitespace and words, escape
+/// sequences included in either.
+struct WordSplit<'a> {
+    rest: &'a str,
+}
+
+impl<'a> WordSplit<'a> {
+    fn new(s: &'a str) -> Self {
+        WordSplit { rest: s }
+    }
+
+    fn next_space(&mut self) -> &'a str {
+        self.take_while(|c| c.is_whitespace())
+    }
+
+    fn next_word(&mut self) -> Option<&'a str> {
+        Some(self.take_while(|c| !c.is_whitespace())).filter(|word| !word.is_empty())
+    }
+
+    /// Take the text up to the first visible character not matching `pred`.
+    fn take_while<P: Fn(char) -> bool>(&mut self, pred: P) -> &'a str {
+        let mut len = 0;
+        for segment in segments(self.rest) {
+            match segment {
+                Segment::Escape(escape) => len += escape.len(),
+                Segment::Text(text) => match text.find(|c| !pred(c)) {
+                    Some(idx) => {
+                        len += idx;
+                        break;
+                    }
+                    None => len += text.len(),
+                },
+            }
+        }
+
+        let (taken, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        taken
+    }
+}
+
+/// Whether an SGR sequence (`ESC [ params m`) resets all attributes, or
+/// `None` for other sequences.
+fn sgr_resets(escape: &str) -> Option<bool> {
+    if !escape.starts_with("\x1B[") || !escape.ends_with('m') {
+        return None;
+    }
+
+    // only the last parameter matters: `1;0` resets, `0;1` doesn't
+    let params = &escape[2..escape.len() - 1];
+    params
+        .rsplit(';')
+        .next()
+        .map(|param| param.is_empty() || param == "0")
+}
+
+/// The URI of an OSC 8 hyperlink sequence (`ESC ] 8 ; params ; uri ST`), an
+/// empty one closing the current link.
+fn hyperlink_uri(escape: &str) -> Option<&str> {
+    if !escape.starts_with("\x1B]8;") {
+        return None;
+    }
+
+    let body = escape[4..]
+        .trim_end_matches('\x07')
+        .trim_end_matches("\x1B\\");
+    body.find(';').map(|idx| &body[idx + 1..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_segments() {
+        let s = "a\x1B[1;31mbc\x1B[0m\x1B]8;;http://x\x07d\x1B(B";
+        assert_eq!(
+            vec![
+                Segment::Text("a"),
+                Segment::Escape("\x1B[1;31m"),
+                Segment::Text("bc"),
+                Segment::Escape("\x1B[0m"),
+                Segment::Escape("\x1B]8;;http://x\x07"),
+                Segment::Text("d"),
+                Segment::Escape("\x1B("),
+                Segment::Text("B"),
+            ],
+            segments(s).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn unterminated_sequences_are_text() {
+        assert_eq!(
+            vec![
+                Segment::Text("ab"),
+                Segment::Text("\x1B"),
+                Segment::Text("[31"),
+            ],
+            segments("ab\x1B[31").collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![Segment::Text("\x1B")],
+            segments("\x1B").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn width() {
+        assert_eq!(0, visible_width(""));
+        assert_eq!(5, visible_width("hello"));
+        assert_eq!(5, visible_width("\x1B[31mhello\x1B[0m"));
+        assert_eq!(4, visible_width("日本"));
+        assert_eq!(7, visible_width("\x1B[1m日本\x1B[0m ok"));
+        assert_eq!(2, visible_width("🦀"));
+    }
+
+    mod wrap {
+        use super::super::wrap;
+
+        #[test]
+        fn plain() {
+            assert_eq!(
+                vec!["the quick", "brown fox", "jumps"],
+                wrap("the quick brown fox jumps", 10)
+            );
+            assert_eq!(vec!["a  b"], wrap("a  b", 10));
+            assert_eq!(vec![""], wrap("", 10));
+        }
+
+        #[test]
+        fn newlines_and_indentation() {
+            assert_eq!(
+                vec!["one", "  two", "three", "", "four"],
+                wrap("one\n  two three\n\nfour", 6)
+            );
+        }
+
+        #[test]
+        fn long_words() {
+            assert_eq!(vec!["a", "abcd", "efgh", "ij b"], wrap("a abcdefghij b", 4));
+            assert_eq!(vec!["日本", "語"], wrap("日本語", 5));
+        }
+
+        #[test]
+        fn reopens_styles() {
+            assert_eq!(
+                vec![
+                    "\x1B[1m\x1B[31mred\x1B[0m",
+                    "\x1B[1m\x1B[31mbold\x1B[0m",
+                    "\x1B[1m\x1B[31mtext\x1B[0m",
+                ],
+                wrap("\x1B[1m\x1B[31mred bold text\x1B[0m", 5)
+            );
+        }
+
+        #[test]
+        fn styles_closed_on_their_line() {
+            assert_eq!(
+                vec!["\x1B[32mgreen\x1B[0m", "plain"],
+                wrap("\x1B[32mgreen\x1B[0m plain", 6)
+            );
+            assert_eq!(
+                vec!["\x1B[32mab\x1B[0m", "\x1B[32mcd\x1B[0m"],
+                wrap("\x1B[32mabcd\x1B[0m", 2)
+            );
+        }
+
+        #[test]
+        fn reset_in_trailing_spaces() {
+            assert_eq!(
+                vec!["\x1B[32mab\x1B[0m", "cd"],
+                wrap("\x1B[32mab \x1B[0m\ncd", 10)
+            );
+        }
+    }
+
+    mod truncate_visible {
+        use super::super::truncate_visible;
+
+        #[test]
+        fn short_enough() {
+            assert_eq!("abc", truncate_visible("abc", 3, "…"));
+            assert_eq!("\x1B[31mabc\x1B[0m", truncate_visible("\x1B[31mabc\x1B[0m", 3, "…"));
+        }
+
+        #[test]
+        fn plain() {
+            assert_eq!("ab…", truncate_visible("abcdef", 3, "…"));
+            assert_eq!("abc", truncate_visible("abcdef", 3, ""));
+            assert_eq!("a...", truncate_visible("abcdef", 4, "..."));
+            assert_eq!("..", truncate_visible("abcdef", 2, "..."));
+            assert_eq!("", truncate_visible("abcdef", 0, "…"));
+        }
+
+        #[test]
+        fn never_cuts_inside_sequences() {
+            let s = "ab\x1B[38;2;10;20;30mcdef\x1B[0m";
+            assert_eq!("ab\x1B[0m", truncate_visible("ab\x1B[0mcd", 2, ""));
+            assert_eq!("ab\x1B[38;2;10;20;30mc\x1B[0m", truncate_visible(s, 3, ""));
+        }
+
+        #[test]
+        fn closes_open_styles() {
+            assert_eq!(
+                "\x1B[1m\x1B[31mab…\x1B[0m",
+                truncate_visible("\x1B[1m\x1B[31mabcd\x1B[0m", 3, "…")
+            );
+            assert_eq!(
+                "\x1B[31mab\x1B[0mc…",
+                truncate_visible("\x1B[31mab\x1B[0mcde", 4, "…")
+            );
+        }
+
+        #[test]
+        fn closes_open_hyperlinks() {
+            let link = "\x1B]8;;https://example.com\x1B\\link text\x1B]8;;\x1B\\";
+            assert_eq!(
+                "\x1B]8;;https://example.com\x1B\\lin\x1B]8;;\x1B\\",
+                truncate_visible(link, 3, "")
+            );
+        }
+
+        #[test]
+        fn wide_characters_and_graphemes() {
+            assert_eq!("日…", truncate_visible("日本語", 4, "…"));
+            assert_eq!("日本", truncate_visible("日本語", 5, ""));
+            // a family emoji is a single grapheme
+            assert_eq!("", truncate_visible("👨‍👩‍👧", 1, ""));
+            assert_eq!("e\u{301}", truncate_visible("e\u{301}e\u{301}", 1, ""));
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use std::env;
+use std::time::Duration;
+
+/// Whether the background of a terminal is light or dark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Background {
+    /// A light background, such as white, which needs dark colors.
+    Light,
+    /// A dark background, such as black, which needs light colors.
+    Dark,
+}
+
+impl Background {
+    /// The background of the given color, light if its perceived brightness,
+    /// weighted on the non-linear sRGB channels, is over one half.
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        let brightness = 0.2126 * f32::from(r) + 0.7152 * f32::from(g) + 0.0722 * f32::from(b);
+        if brightness > 127.5 {
+            Background::Light
+        } else {
+            Background::Dark
+        }
+    }
+}
+
+/// How long to wait for the terminal to reply.
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Ask the terminal whether its background is light or dark.
+///
+/// The terminal is queried with the OSC 11 escape sequence, written to
+/// `/dev/tty`, and its reply waited for at most 100ms. When it doesn't reply,
+/// or there is no terminal at all, the `COLORFGBG` environment variable set
+/// by some terminals is used instead.
+///
+/// This is opt-in: call it once at startup, before reading from the
+/// terminal, as a terminal replying after the timeout writes its reply to
+/// the input of the program.
+///
+/// ```no_run
+/// use colored::control::{self, Background};
+/// use colored::*;
+///
+/// let highlight = match control::query_background() {
+///     Some(Background::Light) => "note".blue(),
+///     Some(Background::Dark) | None => "note".bright_cyan(),
+/// };
+/// println!("{}", highlight);
+/// ```
+pub fn query_background() -> Option<Background> {
+    query_tty().or_else(|| colorfgbg(env::var("COLORFGBG").ok()?.as_str()))
+}
+
+#[cfg(unix)]
+fn query_tty() -> Option<Background> {
+    let tty = ::std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    if !unix::is_foreground(&tty) {
+        return None;
+    }
+    unix::query_osc11(&tty, TIMEOUT)
+}
+
+#[cfg(not(unix))]
+fn query_tty() -> Option<Background> {
+    None
+}
+
+/// Parse `COLORFGBG`, such as `15;0` or `0;default;15`, whose last field is
+/// the background among the 16 basic colors.
+fn colorfgbg(value: &str) -> Option<Background> {
+    let bg: u8 = value.rsplit(';').next()?.parse().ok()?;
+    match bg {
+        0..=6 | 8 => Some(Background::Dark),
+        7 | 9..=15 => Some(Background::Light),
+        _ => None,
+    }
+}
+
+/// Parse the reply to OSC 11, `ESC ] 11 ; rgb:rrrr/gggg/bbbb` followed by
+/// BEL or `ESC \`, where each component has 1 to 4 hex digits.
+fn parse_osc11_reply(reply: &[u8]) -> Option<Background> {
+    let reply = ::std::str::from_utf8(reply).ok()?;
+    let start = reply.find("\x1B]11;rgb:")? + "\x1B]11;rgb:".len();
+    let rest = &reply[start..];
+    let end = rest.find(['\x07', '\x1B'])?;
+
+    let mut components = rest[..end].split('/').map(|component| {
+        if component.is_empty() || component.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(component, 16).ok()?;
+        let max = (1 << (4 * component.len())) - 1;
+        Some((value * 255 / max) as u8)
+    });
+    let r = components.next()??;
+    let g = components.next()??;
+    let b = components.next()??;
+    if components.next().is_some() {
+        return None;
+    }
+    Some(Background::from_rgb(r, g, b))
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::fs::File;
+    use std::io::{self, Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::time::{Duration, Instant};
+
+    use libc;
+
+    use super::{parse_osc11_reply, Background};
+
+    /// Puts the terminal back in its previous mode when dropped.
+    struct RestoreMode<'a> {
+        tty: &'a File,
+        termios: libc::termios,
+    }
+
+    impl<'a> Drop for RestoreMode<'a> {
+        fn drop(&mut self) {
+            unsafe {
+                libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSANOW, &self.termios);
+            }
+        }
+    }
+
+    /// Whether the process is in the foreground process group of `tty`. The
+    /// others are stopped by SIGTTOU or SIGTTIN when they change its mode or
+    /// read from it.
+    pub fn is_foreground(tty: &File) -> bool {
+        let foreground = unsafe { libc::tcgetpgrp(tty.as_raw_fd()) };
+        foreground != -1 && foreground == unsafe { libc::getpgrp() }
+    }
+
+    /// Write the OSC 11 query to `tty` and read the reply, with the echo and
+    /// the line buffering of the terminal turned off meanwhile.
+    pub fn query_osc11(tty: &File, timeout: Duration) -> Option<Background> {
+        let fd = tty.as_raw_fd();
+        let mut termios: libc::termios = unsafe { ::std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut termios) } != 0 {
+            return None;
+        }
+        let _restore = RestoreMode { tty, termios };
+
+        let mut raw = termios;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+            return None;
+        }
+
+        let mut writer = tty;
+        writer.write_all(b"\x1B]11;?\x1B\\").ok()?;
+        writer.flush().ok()?;
+
+        let deadline = Instant::now() + timeout;
+        let mut reply = Vec::new();
+        let mut buf = [0; 64];
+        loop {
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+            let mut pollfd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let millis = remaining.as_millis().clamp(1, libc::c_int::MAX as u128) as libc::c_int;
+            // on timeout or on a signal, the deadline is checked again at the
+            // start of the loop
+            match unsafe { libc::poll(&mut pollfd, 1, millis) } {
+                0 => continue,
+                ready if ready < 0 => {
+                    if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    return None;
+                }
+                _ => (),
+            }
+
+            let mut reader = tty;
+            let read = match reader.read(&mut buf) {
+                Ok(read) => read,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => return None,
+            };
+            if read == 0 {
+                return None;
+            }
+            reply.extend_from_slice(&buf[..read]);
+            if let Some(background) = parse_osc11_reply(&reply) {
+                return Some(background);
+            }
+            // stop at the end of a reply which can't be parsed
+            if reply.ends_with(b"\x07") || reply.ends_with(b"\x1B\\") {
+                return None;
+            }
+        }
+    }
+
+    #[cfg(all(test, target_os = "linux"))]
+    mod tests {
+        use super::*;
+        use std::ffi::CStr;
+        use std::fs::OpenOptions;
+        use std::os::unix::io::FromRawFd;
+        use std::thread;
+
+        /// Open a pseudo-terminal, returning the side of the terminal emulator
+        /// and the side of the program.
+        fn open_pty() -> (File, File) {
+            unsafe {
+                let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+                assert!(master >= 0);
+                assert_eq!(0, libc::grantpt(master));
+                assert_eq!(0, libc::unlockpt(master));
+                // `ptsname` returns a static buffer, shared with the other tests
+                let mut name = [0; 64];
+                assert_eq!(0, libc::ptsname_r(master, name.as_mut_ptr(), name.len()));
+                let name = CStr::from_ptr(name.as_ptr()).to_str().unwrap().to_string();
+                let slave = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .open(name)
+                    .unwrap();
+                (File::from_raw_fd(master), slave)
+            }
+        }
+
+        /// Play a terminal emulator replying `reply` to the query.
+        fn query_with_reply(reply: &'static [u8]) -> Option<Background> {
+            let (mut master, slave) = open_pty();
+            let emulator = thread::spawn(move || {
+                let mut query = [0; 8];
+                master.read_exact(&mut query).unwrap();
+                assert_eq!(b"\x1B]11;?\x1B\\", &query);
+                master.write_all(reply).unwrap();
+                master
+            });
+            let background = query_osc11(&slave, Duration::from_secs(5));
+            drop(emulator.join().unwrap());
+            background
+        }
+
+        #[test]
+        fn replies() {
+            assert_eq!(
+                Some(Background::Light),
+                query_with_reply(b"\x1B]11;rgb:ffff/ffff/ffff\x1B\\")
+            );
+            assert_eq!(
+                Some(Background::Dark),
+                query_with_reply(b"\x1B]11;rgb:1c1c/1c1c/1c1c\x07")
+            );
+            assert_eq!(None, query_with_reply(b"\x1B]11;nonsense\x07"));
+        }
+
+        #[test]
+        fn timeout() {
+            let (_master, slave) = open_pty();
+            let start = Instant::now();
+            assert_eq!(None, query_osc11(&slave, Duration::from_millis(50)));
+            assert!(start.elapsed() >= Duration::from_millis(50));
+        }
+
+        #[test]
+        fn retries_when_interrupted() {
+            extern "C" fn ignore(_: libc::c_int) {}
+            // without SA_RESTART, so that the signal interrupts `poll`
+            let previous = unsafe {
+                let mut action: libc::sigaction = ::std::mem::zeroed();
+                action.sa_sigaction = ignore as extern "C" fn(libc::c_int) as libc::sighandler_t;
+                let mut previous: libc::sigaction = ::std::mem::zeroed();
+                libc::sigaction(libc::SIGUSR1, &action, &mut previous);
+                previous
+            };
+
+            let querier = unsafe { libc::pthread_self() };
+            let (mut master, slave) = open_pty();
+            let emulator = thread::spawn(move || {
+                let mut query = [0; 8];
+                master.read_exact(&mut query).unwrap();
+                thread::sleep(Duration::from_millis(20));
+                unsafe { libc::pthread_kill(querier, libc::SIGUSR1) };
+                thread::sleep(Duration::from_millis(20));
+                master.write_all(b"\x1B]11;rgb:ffff/ffff/ffff\x07").unwrap();
+                master
+            });
+            let background = query_osc11(&slave, Duration::from_secs(5));
+            drop(emulator.join().unwrap());
+            unsafe { libc::sigaction(libc::SIGUSR1, &previous, ::std::ptr::null_mut()) };
+            assert_eq!(Some(Background::Light), background);
+        }
+
+        #[test]
+        fn not_the_controlling_terminal() {
+            let (_master, slave) = open_pty();
+            assert!(!is_foreground(&slave));
+        }
+
+        #[test]
+        fn restores_the_mode() {
+            let (_master, slave) = open_pty();
+            let mode = || unsafe {
+                let mut termios: libc::termios = ::std::mem::zeroed();
+                libc::tcgetattr(slave.as_raw_fd(), &mut termios);
+                termios.c_lflag
+            };
+            let before = mode();
+            query_osc11(&slave, Duration::from_millis(10));
+            assert_eq!(before, mode());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_rgb() {
+        assert_eq!(Background::Light, Background::from_rgb(255, 255, 255));
+        assert_eq!(Background::Light, Background::from_rgb(253, 246, 227));
+        assert_eq!(Background::Dark, Background::from_rgb(0, 43, 54));
+        assert_eq!(Background::Dark, Background::from_rgb(0, 0, 255));
+    }
+
+    #[test]
+    fn osc11_replies() {
+        assert_eq!(
+            Some(Background::Light),
+            parse_osc11_reply(b"\x1B]11;rgb:ffff/ffff/ffff\x1B\\")
+        );
+        assert_eq!(
+            Some(Background::Dark),
+            parse_osc11_reply(b"\x1B]11;rgb:0000/2b2b/3636\x07")
+        );
+        assert_eq!(
+            Some(Background::Light),
+            parse_osc11_reply(b"\x1B]11;rgb:f/ff/fff\x07")
+        );
+        assert_eq!(None, parse_osc11_reply(b"\x1B]11;rgb:ffff/ffff/ffff"));
+        assert_eq!(None, parse_osc11_reply(b"\x1B]11;rgb:ffff/ffff\x07"));
+        assert_eq!(None, parse_osc11_reply(b"\x1B]11;rgb:fffff/0/0\x07"));
+        assert_eq!(None, parse_osc11_reply(b"\x1B]11;rgb:gg/0/0\x07"));
+    }
+
+    #[test]
+    fn colorfgbg_values() {
+        assert_eq!(Some(Background::Dark), colorfgbg("15;0"));
+        assert_eq!(Some(Background::Light), colorfgbg("0;15"));
+        assert_eq!(Some(Background::Light), colorfgbg("0;default;7"));
+        assert_eq!(Some(Background::Dark), colorfgbg("7;8"));
+        assert_eq!(None, colorfgbg("15;default"));
+        assert_eq!(None, colorfgbg(""));
+    }
+}
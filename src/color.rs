@@ -1,4 +1,8 @@
-use std::{borrow::Cow, str::FromStr};
+use alloc::{borrow::Cow, string::String};
+use core::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 /// The 8 standard colors.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -70,7 +74,7 @@ impl Color {
     }
 }
 
-impl<'a> From<&'a str> for Color {
+impl From<&str> for Color {
     fn from(src: &str) -> Self {
         src.parse().unwrap_or(Color::White)
     }
@@ -106,11 +110,67 @@ impl FromStr for Color {
             "bright magenta" => Ok(Color::BrightMagenta),
             "bright cyan" => Ok(Color::BrightCyan),
             "bright white" => Ok(Color::BrightWhite),
+            hex if hex.starts_with('#') => Color::from_hex(&hex[1..]),
             _ => Err(()),
         }
     }
 }
 
+impl Color {
+    /// The name `FromStr` parses back into the same color.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn to_name(self) -> Cow<'static, str> {
+        match self {
+            Color::Black => "black".into(),
+            Color::Red => "red".into(),
+            Color::Green => "green".into(),
+            Color::Yellow => "yellow".into(),
+            Color::Blue => "blue".into(),
+            Color::Magenta => "magenta".into(),
+            Color::Cyan => "cyan".into(),
+            Color::White => "white".into(),
+            Color::BrightBlack => "bright black".into(),
+            Color::BrightRed => "bright red".into(),
+            Color::BrightGreen => "bright green".into(),
+            Color::BrightYellow => "bright yellow".into(),
+            Color::BrightBlue => "bright blue".into(),
+            Color::BrightMagenta => "bright magenta".into(),
+            Color::BrightCyan => "bright cyan".into(),
+            Color::BrightWhite => "bright white".into(),
+            Color::TrueColor { r, g, b } => format!("#{:02x}{:02x}{:02x}", r, g, b).into(),
+        }
+    }
+
+    fn from_hex(hex: &str) -> Result<Self, ()> {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(());
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| ());
+        Ok(Color::TrueColor {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let src = String::deserialize(deserializer)?;
+        src.parse()
+            .map_err(|_| de::Error::custom(format_args!("unknown color `{}`", src)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     pub use super::*;
@@ -149,8 +209,12 @@ mod tests {
             brightmagenta: "bright magenta" => Color::BrightMagenta,
             brightcyan: "bright cyan" => Color::BrightCyan,
             brightwhite: "bright white" => Color::BrightWhite,
+            hex: "#ff8800" => Color::TrueColor { r: 255, g: 136, b: 0 },
+            hex_uppercase: "#00AAff" => Color::TrueColor { r: 0, g: 170, b: 255 },
 
             invalid: "invalid" => Color::White,
+            invalid_hex: "#ff88" => Color::White,
+            invalid_hex_digits: "#gg8800" => Color::White,
             capitalized: "BLUE" => Color::Blue,
             mixed_case: "bLuE" => Color::Blue
         );
@@ -211,5 +275,58 @@ mod tests {
             let color: Result<Color, ()> = "bloublou".parse();
             assert_eq!(Err(()), color)
         }
+
+        #[test]
+        fn name_round_trip() {
+            let colors = [
+                Color::Black,
+                Color::Magenta,
+                Color::BrightBlack,
+                Color::BrightWhite,
+                Color::TrueColor { r: 0, g: 128, b: 255 },
+            ];
+            for &color in colors.iter() {
+                assert_eq!(Ok(color), color.to_name().parse());
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    mod serialize {
+        extern crate serde_json;
+
+        pub use super::*;
+
+        #[test]
+        fn serialize() {
+            assert_eq!("\"red\"", serde_json::to_string(&Color::Red).unwrap());
+            assert_eq!(
+                "\"bright blue\"",
+                serde_json::to_string(&Color::BrightBlue).unwrap()
+            );
+            assert_eq!(
+                "\"#0a80ff\"",
+                serde_json::to_string(&Color::TrueColor { r: 10, g: 128, b: 255 }).unwrap()
+            );
+        }
+
+        #[test]
+        fn round_trip() {
+            let colors = [
+                Color::Green,
+                Color::BrightYellow,
+                Color::TrueColor { r: 1, g: 2, b: 3 },
+            ];
+            for color in colors.iter() {
+                let json = serde_json::to_string(color).unwrap();
+                assert_eq!(*color, serde_json::from_str::<Color>(&json).unwrap());
+            }
+        }
+
+        #[test]
+        fn unknown_color() {
+            let err = serde_json::from_str::<Color>("\"bloublou\"").unwrap_err();
+            assert!(err.to_string().contains("unknown color `bloublou`"));
+        }
     }
 }
@@ -0,0 +1,227 @@
+use alloc::{borrow::Cow, string::String};
+use core::{fmt, ops::Deref};
+
+use color::Color;
+use paste::paste;
+use style::{Style, Styles, NO_STYLE};
+use ColoredString;
+
+/// A [`ColoredString`] that borrows its input when it can.
+///
+/// Coloring a `&str` through [`Colorize`](crate::Colorize) copies it into a
+/// `String`. `ColoredStr` keeps a `Cow` instead, so large borrowed inputs are
+/// never copied, and owned ones are moved in.
+///
+/// ```rust
+/// # use colored::*;
+/// let text = String::from("a very long text");
+/// let cstr = ColoredStr::from(text.as_str()).red().bold();
+/// assert_eq!(&*cstr, "a very long text");
+/// assert_eq!(cstr.to_string(), "a very long text".red().bold().to_string());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColoredStr<'a> {
+    input: Cow<'a, str>,
+    fgcolor: Option<Color>,
+    bgcolor: Option<Color>,
+    style: Style,
+}
+
+impl<'a> ColoredStr<'a> {
+    /// Set the foreground color.
+    pub fn color<S: Into<Color>>(mut self, color: S) -> Self {
+        self.fgcolor = Some(color.into());
+        self
+    }
+
+    /// Set the background color.
+    pub fn on_color<S: Into<Color>>(mut self, color: S) -> Self {
+        self.bgcolor = Some(color.into());
+        self
+    }
+
+    /// Add styles on top of the current ones.
+    pub fn add_style<S: Into<Style>>(mut self, style: S) -> Self {
+        self.style += style.into();
+        self
+    }
+
+    /// Remove styles from the current ones.
+    pub fn remove_style<S: Into<Style>>(mut self, style: S) -> Self {
+        self.style -= style.into();
+        self
+    }
+
+    /// Remove all colors and styles.
+    pub fn clear(self) -> Self {
+        ColoredStr::from(self.input)
+    }
+
+    painting_methods!(
+        Black, Red, Green, Yellow, Blue, Magenta, Cyan, White;
+        Bold, Dimmed, Italic, Underline, Blink, Reversed, Hidden, Strikethrough,
+    );
+
+    /// Get the current foreground color applied.
+    pub fn fgcolor(&self) -> Option<Color> {
+        self.fgcolor
+    }
+
+    /// Get the current background color applied.
+    pub fn bgcolor(&self) -> Option<Color> {
+        self.bgcolor
+    }
+
+    /// Get the current [`Style`].
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    /// Checks if the string has no color or styling.
+    pub fn is_plain(&self) -> bool {
+        self.bgcolor.is_none() && self.fgcolor.is_none() && self.style == NO_STYLE
+    }
+
+    /// Turn into a [`ColoredString`], copying the input if it is borrowed.
+    pub fn into_owned(self) -> ColoredString {
+        ColoredString {
+            input: self.input.into_owned(),
+            fgcolor: self.fgcolor,
+            bgcolor: self.bgcolor,
+            style: self.style,
+        }
+    }
+}
+
+impl ColoredString {
+    /// Borrow the string as a [`ColoredStr`], with the same colors and style.
+    ///
+    /// ```rust
+    /// # use colored::*;
+    /// let cstr = "hello".red().bold();
+    /// assert_eq!(cstr.as_colored_str().fgcolor(), Some(Color::Red));
+    /// assert_eq!(cstr.as_colored_str().to_string(), cstr.to_string());
+    /// ```
+    pub fn as_colored_str(&self) -> ColoredStr<'_> {
+        ColoredStr {
+            input: Cow::Borrowed(&self.input),
+            fgcolor: self.fgcolor,
+            bgcolor: self.bgcolor,
+            style: self.style,
+        }
+    }
+}
+
+impl<'a> Default for ColoredStr<'a> {
+    fn default() -> Self {
+        ColoredStr::from("")
+    }
+}
+
+impl<'a> Deref for ColoredStr<'a> {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.input
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for ColoredStr<'a> {
+    fn from(input: Cow<'a, str>) -> Self {
+        ColoredStr {
+            input,
+            fgcolor: None,
+            bgcolor: None,
+            style: NO_STYLE,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for ColoredStr<'a> {
+    fn from(input: &'a str) -> Self {
+        ColoredStr::from(Cow::Borrowed(input))
+    }
+}
+
+impl<'a> From<String> for ColoredStr<'a> {
+    fn from(input: String) -> Self {
+        ColoredStr::from(Cow::Owned(input))
+    }
+}
+
+impl<'a> From<ColoredString> for ColoredStr<'a> {
+    fn from(s: ColoredString) -> Self {
+        ColoredStr {
+            input: Cow::Owned(s.input),
+            fgcolor: s.fgcolor,
+            bgcolor: s.bgcolor,
+            style: s.style,
+        }
+    }
+}
+
+impl<'a> fmt::Display for ColoredStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        ::fmt_colored(
+            f,
+            ::should_colorize(),
+            &self.input,
+            self.fgcolor,
+            self.bgcolor,
+            self.style,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use control;
+    use Colorize;
+
+    #[test]
+    fn borrows_input() {
+        let input = String::from("borrowed");
+        let cstr = ColoredStr::from(input.as_str()).blue();
+
+        assert!(match cstr.input {
+            Cow::Borrowed(s) => s.as_ptr() == input.as_ptr(),
+            Cow::Owned(_) => false,
+        });
+    }
+
+    #[test]
+    fn same_attributes_as_colored_string() {
+        let cstr = ColoredStr::from("x").red().on_white().italic().clear().green();
+        assert_eq!("x".red().on_white().italic().clear().green(), cstr.into_owned());
+    }
+
+    #[test]
+    fn round_trip() {
+        let cstring = "hello".bright_yellow().on_blue().underline();
+        assert_eq!(cstring, ColoredStr::from(cstring.clone()).into_owned());
+        assert_eq!(cstring, ColoredString::from(cstring.as_colored_str()));
+    }
+
+    #[test]
+    fn deref() {
+        let cstr = ColoredStr::from("deref").bold();
+        assert_eq!(5, cstr.len());
+        assert!(cstr.starts_with("de"));
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn same_output_as_colored_string() {
+        control::with_colors(true, || {
+            let nested = format!("start {} end", "nested".red());
+            assert_eq!(
+                nested.as_str().blue().to_string(),
+                ColoredStr::from(nested.as_str()).blue().to_string()
+            );
+            assert_eq!(
+                format!("{:>10.3}", "toto".on_green()),
+                format!("{:>10.3}", ColoredStr::from("toto").on_green())
+            );
+        });
+    }
+}
@@ -0,0 +1,468 @@
+use alloc::{string::String, vec, vec::Vec};
+use core::{
+    fmt,
+    iter::FromIterator,
+    ops::{Add, AddAssign, Bound, RangeBounds},
+    slice,
+};
+
+use ansi::{self, Segment};
+use formatters::{ColoringFormatter, FormatWith};
+use style::NO_STYLE;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+use {Attributes, ColoredString};
+
+/// A sequence of [`ColoredString`]s, each with its own colors and style.
+///
+/// Use it to build a line out of differently colored parts without
+/// formatting each of them into a `String` first. When displayed, only the
+/// colors and styles changing from one span to the next are written, instead
+/// of resetting everything after each span.
+///
+/// ```rust
+/// # use colored::*;
+/// let mut line = "error".red().bold() + ": ";
+/// line += "file not found".normal();
+/// line.push(" (src/main.rs)".dimmed());
+///
+/// assert_eq!(line.len_visible(), 35);
+/// println!("{}", line);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColoredStrings {
+    spans: Vec<ColoredString>,
+}
+
+impl ColoredStrings {
+    /// Create an empty sequence.
+    pub fn new() -> Self {
+        ColoredStrings::default()
+    }
+
+    /// Append a span at the end of the sequence.
+    pub fn push<S: Into<ColoredString>>(&mut self, span: S) {
+        self.spans.push(span.into());
+    }
+
+    /// Get the spans of the sequence.
+    pub fn spans(&self) -> &[ColoredString] {
+        &self.spans
+    }
+
+    /// Iterate over the spans of the sequence.
+    pub fn iter(&self) -> slice::Iter<'_, ColoredString> {
+        self.spans.iter()
+    }
+
+    /// Checks if the sequence has no visible text.
+    pub fn is_empty(&self) -> bool {
+        self.spans.iter().all(|span| span.input.is_empty())
+    }
+
+    /// The number of columns the sequence takes on a terminal. See
+    /// [`ansi::visible_width`].
+    pub fn len_visible(&self) -> usize {
+        self.spans
+            .iter()
+            .map(|span| ansi::visible_width(&span.input))
+            .sum()
+    }
+
+    /// Cut the sequence to the given range of visible columns, keeping the
+    /// colors and style of each span.
+    ///
+    /// A wide character which would only partly fit in the range is left out,
+    /// as are the spans with nothing left in the range.
+    ///
+    /// ```rust
+    /// # use colored::*;
+    /// let line = "hello ".red() + "world".blue();
+    /// assert_eq!(line.slice(3..8), "lo ".red() + "wo".blue());
+    /// ```
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> ColoredStrings {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => usize::MAX,
+        };
+
+        let mut sliced = ColoredStrings::new();
+        let mut offset = 0;
+        for span in &self.spans {
+            let width = ansi::visible_width(&span.input);
+            if offset < end && start < offset + width {
+                let input = slice_visible(&span.input, start.saturating_sub(offset), end - offset);
+                if !input.is_empty() {
+                    sliced.push(ColoredString {
+                        input,
+                        ..span.clone()
+                    });
+                }
+            }
+            offset += width;
+        }
+        sliced
+    }
+
+    /// Display the sequence through `formatter`, such as
+    /// [`formatters::Html`](crate::formatters::Html), instead of the
+    /// formatter set with
+    /// [`formatters::set_formatter`](crate::formatters::set_formatter). The
+    /// colors are written whatever [`control`](crate::control) decides,
+    /// unless the `no-color` feature is enabled.
+    pub fn format_with<'a>(
+        &'a self,
+        formatter: &'a dyn ColoringFormatter,
+    ) -> FormatWith<'a, ColoredStrings> {
+        FormatWith {
+            value: self,
+            formatter,
+        }
+    }
+
+    /// Write each span through `formatter`, along with the padding of `f`,
+    /// without their colors and style unless `colorize` is true.
+    fn fmt_with(
+        &self,
+        f: &mut fmt::Formatter,
+        formatter: &dyn ColoringFormatter,
+        colorize: bool,
+    ) -> fmt::Result {
+        let sliced;
+        let spans = match f.precision() {
+            Some(precision) => {
+                sliced = self.slice(..precision);
+                &sliced
+            }
+            None => self,
+        };
+
+        let (before, after) = ::padding(f, spans.len_visible());
+        let fill = f.fill();
+        let fill_with = |f: &mut fmt::Formatter, count: usize| {
+            if count == 0 {
+                return Ok(());
+            }
+            let fill: String = ::core::iter::repeat_n(fill, count).collect();
+            formatter.format(f, &fill, None, None, NO_STYLE)
+        };
+
+        fill_with(f, before)?;
+        for span in spans {
+            if colorize {
+                formatter.format(f, &span.input, span.fgcolor, span.bgcolor, span.style)?;
+            } else {
+                formatter.format(f, &span.input, None, None, NO_STYLE)?;
+            }
+        }
+        fill_with(f, after)
+    }
+
+    fn write_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        let colorize = ::should_colorize();
+        let mut current = Attributes::PLAIN;
+        // the full style of `current`, to go back to after nested resets
+        let mut style_str = String::new();
+        // whether the last span has escape sequences of its own
+        let mut nested = false;
+
+        for span in &self.spans {
+            let attributes = if colorize {
+                span.attributes()
+            } else {
+                Attributes::PLAIN
+            };
+            // the escape sequences of a nested colored string may leave other
+            // colors or styles on, so the span after it starts over
+            if nested && current != Attributes::PLAIN {
+                w.write_str("\x1B[0m")?;
+                current = Attributes::PLAIN;
+            }
+            if attributes != current {
+                ::write_transition(w, current, attributes)?;
+                style_str.clear();
+                ::write_style(
+                    &mut style_str,
+                    attributes.fgcolor,
+                    attributes.bgcolor,
+                    attributes.style,
+                )?;
+                current = attributes;
+            }
+            w.write_str(&::escape_inner_reset_sequences(&span.input, &style_str))?;
+            nested = span.input.contains('\x1B');
+        }
+
+        ::write_transition(w, current, Attributes::PLAIN)
+    }
+}
+
+/// Keep the graphemes of `s` between the `start` and `end` visible columns,
+/// along with all of its escape sequences.
+fn slice_visible(s: &str, start: usize, end: usize) -> String {
+    let mut sliced = String::new();
+    let mut column = 0;
+    for segment in ansi::segments(s) {
+        match segment {
+            Segment::Escape(escape) => sliced.push_str(escape),
+            Segment::Text(text) => {
+                for grapheme in text.graphemes(true) {
+                    let width = grapheme.width();
+                    if start <= column && column + width <= end {
+                        sliced.push_str(grapheme);
+                    }
+                    column += width;
+                }
+            }
+        }
+    }
+    sliced
+}
+
+impl From<Vec<ColoredString>> for ColoredStrings {
+    fn from(spans: Vec<ColoredString>) -> Self {
+        ColoredStrings { spans }
+    }
+}
+
+impl From<ColoredString> for ColoredStrings {
+    fn from(span: ColoredString) -> Self {
+        ColoredStrings { spans: vec![span] }
+    }
+}
+
+impl<S: Into<ColoredString>> FromIterator<S> for ColoredStrings {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        ColoredStrings {
+            spans: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<S: Into<ColoredString>> Extend<S> for ColoredStrings {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        self.spans.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+impl IntoIterator for ColoredStrings {
+    type Item = ColoredString;
+    type IntoIter = vec::IntoIter<ColoredString>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.spans.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ColoredStrings {
+    type Item = &'a ColoredString;
+    type IntoIter = slice::Iter<'a, ColoredString>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.spans.iter()
+    }
+}
+
+impl<S: Into<ColoredString>> Add<S> for ColoredString {
+    type Output = ColoredStrings;
+
+    fn add(self, rhs: S) -> ColoredStrings {
+        ColoredStrings {
+            spans: vec![self, rhs.into()],
+        }
+    }
+}
+
+impl<S: Into<ColoredString>> Add<S> for ColoredStrings {
+    type Output = ColoredStrings;
+
+    fn add(mut self, rhs: S) -> ColoredStrings {
+        self.push(rhs);
+        self
+    }
+}
+
+impl<S: Into<ColoredString>> AddAssign<S> for ColoredStrings {
+    fn add_assign(&mut self, rhs: S) {
+        self.push(rhs);
+    }
+}
+
+impl Add<ColoredStrings> for ColoredStrings {
+    type Output = ColoredStrings;
+
+    fn add(mut self, rhs: ColoredStrings) -> ColoredStrings {
+        self.spans.extend(rhs.spans);
+        self
+    }
+}
+
+impl AddAssign<ColoredStrings> for ColoredStrings {
+    fn add_assign(&mut self, rhs: ColoredStrings) {
+        self.spans.extend(rhs.spans);
+    }
+}
+
+/// The width and precision of the formatter apply to the visible width of the
+/// whole sequence.
+impl fmt::Display for ColoredStrings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        #[cfg(feature = "std")]
+        {
+            if let Some(formatter) = ::formatters::global() {
+                return self.fmt_with(f, &*formatter, ::should_colorize());
+            }
+        }
+
+        if f.width().is_none() && f.precision().is_none() {
+            return self.write_to(f);
+        }
+
+        let mut rendered = String::new();
+        self.write_to(&mut rendered)?;
+        let content = match f.precision() {
+            Some(precision) => ansi::truncate_visible(&rendered, precision, ""),
+            None => rendered.as_str().into(),
+        };
+        ::pad_visible(f, &content)
+    }
+}
+
+impl<'a> fmt::Display for FormatWith<'a, ColoredStrings> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value
+            .fmt_with(f, self.formatter, cfg!(not(feature = "no-color")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use control;
+    use Colorize;
+
+    #[test]
+    fn push_and_add() {
+        let mut line = "a".red() + "b".blue();
+        line += "c";
+        line.push("d".green());
+        let line = line + ("e".normal() + "f".bold());
+
+        let inputs: Vec<&str> = line.iter().map(|span| &**span).collect();
+        assert_eq!(vec!["a", "b", "c", "d", "e", "f"], inputs);
+        assert_eq!(Some(::Color::Blue), line.spans()[1].fgcolor());
+    }
+
+    #[test]
+    fn len_visible() {
+        let line: ColoredStrings = vec!["日本".red(), format!("{}", "x".blue()).normal()].into();
+        assert_eq!(5, line.len_visible());
+        assert!(!line.is_empty());
+        assert!(ColoredStrings::from(vec!["".red()]).is_empty());
+    }
+
+    #[test]
+    fn slice() {
+        let line = "abc".red() + "def".blue() + "ghi".green();
+        assert_eq!("bc".red() + "de".blue(), line.slice(1..5));
+        assert_eq!(ColoredStrings::from("ghi".green()), line.slice(6..));
+        assert_eq!("abc".red() + "d".blue(), line.slice(..=3));
+        assert_eq!(line, line.slice(..));
+        assert_eq!(ColoredStrings::new(), line.slice(9..));
+        assert_eq!(line, line.slice(..=usize::MAX));
+    }
+
+    #[test]
+    fn slice_wide_characters() {
+        let line = "日本".red() + "語".blue();
+        assert_eq!(ColoredStrings::from("本".red()), line.slice(1..5));
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn display_merges_identical_neighbours() {
+        control::with_colors(true, || {
+            let line = "a".red() + "b".red() + "c".blue() + "d" + "e".blue();
+            assert_eq!(
+                "\x1B[31mab\x1B[34mc\x1B[0md\x1B[34me\x1B[0m",
+                line.to_string()
+            );
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn display_minimal_transitions() {
+        control::with_colors(true, || {
+            let line = "a".red().bold() + "b".red().underline() + "c".on_blue().underline();
+            assert_eq!(
+                "\x1B[1;31ma\x1B[22;4mb\x1B[44;39mc\x1B[0m",
+                line.to_string()
+            );
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn display_nested() {
+        control::with_colors(true, || {
+            let nested = format!("a{}b", "x".red());
+            let line = nested.as_str().blue() + "c".blue();
+            assert_eq!(
+                "\x1B[34ma\x1B[31mx\x1B[0m\x1B[34mb\x1B[0m\x1B[34mc\x1B[0m",
+                line.to_string()
+            );
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn display_after_nested_sequences() {
+        control::with_colors(true, || {
+            let unclosed = "a\x1B[1mb".blue() + "c".blue();
+            assert_eq!(
+                "\x1B[34ma\x1B[1mb\x1B[0m\x1B[34mc\x1B[0m",
+                unclosed.to_string()
+            );
+
+            let nested = format!("a{}", "x".red()).blue() + "c".blue();
+            assert_eq!(
+                "\x1B[34ma\x1B[31mx\x1B[0m\x1B[34m\x1B[0m\x1B[34mc\x1B[0m",
+                nested.to_string()
+            );
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn display_width_and_precision() {
+        control::with_colors(true, || {
+            let line = "ab".red() + "cd".normal();
+            assert_eq!("\x1B[31mab\x1B[0mcd  ", format!("{:<6}", line));
+            assert_eq!("  \x1B[31mab\x1B[0mc", format!("{:>5.3}", line));
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn format_with() {
+        let line = "ab".red() + "c".normal();
+        assert_eq!(
+            r#"<span style="color:#cd0000">ab</span>c__"#,
+            format!("{:_<5}", line.format_with(&::formatters::Html))
+        );
+        assert_eq!("abc", line.format_with(&::formatters::NoColor).to_string());
+    }
+
+    #[test]
+    fn display_plain() {
+        let line = "ab".normal() + "cd";
+        assert_eq!("abcd", line.to_string());
+    }
+}
@@ -1,8 +1,18 @@
 //! A couple of functions to enable and disable coloring.
 
+use std::cell::Cell;
 use std::default::Default;
 use std::env;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::hint;
+use std::sync::atomic::{self, AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+pub use background::{query_background, Background};
 
 /// Sets a flag to the console to use a virtual terminal environment.
 ///
@@ -60,13 +70,126 @@ pub fn set_virtual_terminal(use_virtual: bool) -> Result<(), ()> {
     Ok(())
 }
 
+/// An output stream, which may go to a terminal while the other one doesn't.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stream {
+    /// The standard output.
+    Stdout,
+    /// The standard error.
+    Stderr,
+}
+
+/// How many colors a terminal supports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorLevel {
+    /// The 16 basic colors.
+    Basic,
+    /// 256 colors.
+    Ansi256,
+    /// 24-bit RGB colors, e.g. [`Color::TrueColor`](crate::Color::TrueColor).
+    TrueColor,
+}
+
+/// An `Option<T>` stored in an `AtomicU8`.
+struct AtomicOption<T> {
+    value: AtomicU8,
+    phantom: PhantomData<T>,
+}
+
+/// A value which fits in a `u8` other than 0, for an [`AtomicOption`].
+trait Packed: Copy {
+    fn pack(self) -> u8;
+    fn unpack(packed: u8) -> Self;
+}
+
+impl Packed for bool {
+    fn pack(self) -> u8 {
+        self as u8 + 1
+    }
+
+    fn unpack(packed: u8) -> Self {
+        packed == 2
+    }
+}
+
+impl Packed for ColorLevel {
+    fn pack(self) -> u8 {
+        self as u8 + 1
+    }
+
+    fn unpack(packed: u8) -> Self {
+        match packed {
+            1 => ColorLevel::Basic,
+            2 => ColorLevel::Ansi256,
+            _ => ColorLevel::TrueColor,
+        }
+    }
+}
+
+impl<T: Packed> AtomicOption<T> {
+    fn new(value: Option<T>) -> Self {
+        AtomicOption {
+            value: AtomicU8::new(value.map_or(0, Packed::pack)),
+            phantom: PhantomData,
+        }
+    }
+
+    fn load(&self) -> Option<T> {
+        match self.value.load(Ordering::Relaxed) {
+            0 => None,
+            packed => Some(T::unpack(packed)),
+        }
+    }
+
+    fn store(&self, value: Option<T>) {
+        self.value
+            .store(value.map_or(0, Packed::pack), Ordering::Relaxed);
+    }
+}
+
 /// A flag to to if coloring should occur.
 pub struct ShouldColorize {
-    clicolor: bool,
-    clicolor_force: Option<bool>,
+    // atomics, so that the environment can be read again through a shared
+    // reference, see `refresh`
+    //
+    // a sequence lock over the fields read from the environment, odd while
+    // they are written, so that a decision never mixes two refreshes
+    sequence: AtomicUsize,
+    clicolor: AtomicBool,
+    stdout_is_tty: AtomicBool,
+    stderr_is_tty: AtomicBool,
+    clicolor_force: AtomicOption<bool>,
+    color_level: AtomicOption<ColorLevel>,
+    ci: AtomicOption<ColorLevel>,
+    #[cfg(feature = "terminfo")]
+    terminfo: AtomicOption<ColorLevel>,
+    ci_detection: AtomicBool,
     // XXX we can't use Option<Atomic> because we can't use &mut references to ShouldColorize
     has_manual_override: AtomicBool,
     manual_override: AtomicBool,
+    scopes: Mutex<Scopes>,
+}
+
+/// The overrides set by the live [`OverrideGuard`]s, oldest first.
+#[derive(Default)]
+struct Scopes {
+    next_id: usize,
+    stack: Vec<Scope>,
+}
+
+struct Scope {
+    id: usize,
+    /// The override to go back to when the guard is dropped.
+    previous: Option<bool>,
+}
+
+/// Restores the previous override of a [`ShouldColorize`] when dropped.
+///
+/// Obtained with [`override_scope`] or [`ShouldColorize::override_scope`].
+#[must_use = "the override is restored as soon as the guard is dropped"]
+pub struct OverrideGuard<'a> {
+    should_colorize: &'a ShouldColorize,
+    id: usize,
 }
 
 /// Use this to force colored to ignore the environment and always/never colorize
@@ -81,6 +204,200 @@ pub fn unset_override() {
     SHOULD_COLORIZE.unset_override()
 }
 
+/// Read the environment variables and check if stdout and stderr are ttys
+/// again, e.g. after changing `NO_COLOR` or redirecting stdout. They are
+/// otherwise only read the first time [`SHOULD_COLORIZE`] is used.
+///
+/// The overrides are kept.
+///
+/// ```rust
+/// use colored::*;
+/// # std::env::remove_var("CLICOLOR_FORCE");
+/// # std::env::remove_var("FORCE_COLOR");
+///
+/// std::env::set_var("NO_COLOR", "1");
+/// control::refresh();
+/// assert!(!control::SHOULD_COLORIZE.should_colorize());
+/// ```
+pub fn refresh() {
+    SHOULD_COLORIZE.refresh()
+}
+
+/// Enable or disable treating stdout and stderr as terminals when running on
+/// a known CI service. Enabled by default.
+pub fn set_ci_detection(enabled: bool) {
+    SHOULD_COLORIZE.set_ci_detection(enabled)
+}
+
+/// Force colored to always/never colorize until the returned guard is
+/// dropped, then go back to the previous override, or to the environment if
+/// there was none.
+///
+/// ```rust
+/// use colored::control::{self, SHOULD_COLORIZE};
+///
+/// control::set_override(false);
+/// {
+///     let _guard = control::override_scope(true);
+///     assert!(SHOULD_COLORIZE.should_colorize());
+/// }
+/// assert!(!SHOULD_COLORIZE.should_colorize());
+/// ```
+pub fn override_scope(override_colorize: bool) -> OverrideGuard<'static> {
+    SHOULD_COLORIZE.override_scope(override_colorize)
+}
+
+/// Run `f` with colors forced on or off for the current thread only, then go
+/// back to the previous state of the thread.
+///
+/// The thread override comes before every other setting, including
+/// [`set_override`]. As tests run in parallel threads, it lets a test check
+/// colored or plain output without interfering with the others.
+///
+/// ```rust
+/// use colored::*;
+///
+/// let output = control::with_colors(false, || "red".red().to_string());
+/// assert_eq!(output, "red");
+/// ```
+pub fn with_colors<F: FnOnce() -> R, R>(colorize: bool, f: F) -> R {
+    /// Restores the previous thread override, even if `f` panics.
+    struct Restore(Option<bool>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            THREAD_OVERRIDE.with(|thread_override| thread_override.set(self.0));
+        }
+    }
+
+    let previous = THREAD_OVERRIDE.with(|thread_override| thread_override.replace(Some(colorize)));
+    let _restore = Restore(previous);
+    f()
+}
+
+/// When to color the output, as chosen with a `--color` flag.
+///
+/// ```rust
+/// use colored::control::ColorChoice;
+///
+/// let choice: ColorChoice = "never".parse().unwrap();
+/// choice.apply();
+/// # colored::control::unset_override();
+/// ```
+///
+/// With the `clap` feature, it implements `clap::ValueEnum`, and
+/// [`ColorChoice::arg`] defines the whole `--color` argument.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorChoice {
+    /// Decide from the environment and the terminal, see
+    /// [`ShouldColorize::from_env`].
+    #[default]
+    Auto,
+    /// Always color the output.
+    Always,
+    /// Never color the output.
+    Never,
+}
+
+impl ColorChoice {
+    /// Set the override of [`SHOULD_COLORIZE`] for this choice, or unset it
+    /// for [`ColorChoice::Auto`].
+    pub fn apply(self) {
+        match self {
+            ColorChoice::Auto => unset_override(),
+            ColorChoice::Always => set_override(true),
+            ColorChoice::Never => set_override(false),
+        }
+    }
+
+    /// The name of the choice, as given on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        }
+    }
+
+    /// The `--color[=WHEN]` argument, `auto` by default and `always` when
+    /// given without a value.
+    ///
+    /// ```rust
+    /// use colored::control::ColorChoice;
+    ///
+    /// let matches = clap::Command::new("app")
+    ///     .arg(ColorChoice::arg())
+    ///     .get_matches_from(["app", "--color=never"]);
+    /// matches.get_one::<ColorChoice>("color").unwrap().apply();
+    /// # colored::control::unset_override();
+    /// ```
+    #[cfg(feature = "clap")]
+    pub fn arg() -> clap::Arg {
+        clap::Arg::new("color")
+            .long("color")
+            .value_name("WHEN")
+            .help("When to color the output")
+            .value_parser(clap::builder::EnumValueParser::<ColorChoice>::new())
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_value("auto")
+            .default_missing_value("always")
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = ParseColorChoiceError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        [ColorChoice::Auto, ColorChoice::Always, ColorChoice::Never]
+            .iter()
+            .find(|choice| choice.name().eq_ignore_ascii_case(src))
+            .cloned()
+            .ok_or_else(|| ParseColorChoiceError {
+                value: src.to_string(),
+            })
+    }
+}
+
+#[cfg(feature = "clap")]
+impl clap::ValueEnum for ColorChoice {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[ColorChoice::Auto, ColorChoice::Always, ColorChoice::Never]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(clap::builder::PossibleValue::new(self.name()))
+    }
+}
+
+/// The error returned when a [`ColorChoice`] can't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseColorChoiceError {
+    value: String,
+}
+
+impl fmt::Display for ParseColorChoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unknown color choice `{}`, expected `auto`, `always` or `never`",
+            self.value
+        )
+    }
+}
+
+impl Error for ParseColorChoiceError {}
+
+thread_local! {
+    static THREAD_OVERRIDE: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
 lazy_static! {
 /// The persistent [`ShouldColorize`].
     pub static ref SHOULD_COLORIZE: ShouldColorize = ShouldColorize::from_env();
@@ -89,10 +406,19 @@ lazy_static! {
 impl Default for ShouldColorize {
     fn default() -> ShouldColorize {
         ShouldColorize {
-            clicolor: true,
-            clicolor_force: None,
+            sequence: AtomicUsize::new(0),
+            clicolor: AtomicBool::new(true),
+            stdout_is_tty: AtomicBool::new(true),
+            stderr_is_tty: AtomicBool::new(true),
+            clicolor_force: AtomicOption::new(None),
+            color_level: AtomicOption::new(None),
+            ci: AtomicOption::new(None),
+            #[cfg(feature = "terminfo")]
+            terminfo: AtomicOption::new(None),
+            ci_detection: AtomicBool::new(true),
             has_manual_override: AtomicBool::new(false),
             manual_override: AtomicBool::new(false),
+            scopes: Mutex::default(),
         }
     }
 }
@@ -100,31 +426,198 @@ impl Default for ShouldColorize {
 impl ShouldColorize {
     /// Reads environment variables and checks if output is a tty to determine
     /// whether colorization should be used or not.
-    /// `CLICOLOR_FORCE` takes highest priority, followed by `NO_COLOR`,
-    /// followed by `CLICOLOR` combined with tty check.
+    ///
+    /// The first row which applies decides:
+    ///
+    /// | Setting                                   | Colors |
+    /// |-------------------------------------------|--------|
+    /// | [`with_colors`] on the current thread     | as set |
+    /// | [`set_override`] or [`override_scope`]    | as set |
+    /// | `CLICOLOR_FORCE` set and not `0`          | yes    |
+    /// | `FORCE_COLOR` set                         | yes, unless `0` or `false` |
+    /// | `NO_COLOR` set and not empty              | no     |
+    /// | `TERM=dumb`                               | no     |
+    /// | `CLICOLOR=0`                              | no     |
+    /// | the stream is a tty, or on a CI service   | yes    |
+    /// | otherwise                                 | no     |
+    ///
+    /// `FORCE_COLOR` is `0` to disable the colors, or `1`, `2` or `3` to
+    /// force them and set the [`ColorLevel`] to basic, 256 colors or true
+    /// colors. An empty `NO_COLOR` is ignored, as in the
+    /// [spec](https://no-color.org).
+    ///
+    /// The tty check is done for both stdout and stderr, see
+    /// [`for_stream`](ShouldColorize::for_stream). On CI services whose logs
+    /// render colors, such as GitHub Actions, GitLab CI or Buildkite, both
+    /// pass the tty check unless disabled with
+    /// [`set_ci_detection`](ShouldColorize::set_ci_detection).
     pub fn from_env() -> Self {
-        ShouldColorize {
-            clicolor: ShouldColorize::normalize_env(env::var("CLICOLOR")).unwrap_or(true)
-                && atty::is(atty::Stream::Stdout),
-            clicolor_force: ShouldColorize::resolve_clicolor_force(
-                env::var("NO_COLOR"),
-                env::var("CLICOLOR_FORCE"),
-            ),
-            ..ShouldColorize::default()
-        }
+        let colorize_control = ShouldColorize::default();
+        colorize_control.refresh();
+        colorize_control
     }
 
-    /// Returns if the current coloring is expected.
+    /// Like [`from_env`](ShouldColorize::from_env), with the environment
+    /// variables looked up by `env` and the tty check done by `is_tty`
+    /// instead of the real ones.
+    ///
+    /// Use it to test the colors of a program without changing the
+    /// environment of the process.
+    ///
+    /// ```rust
+    /// use colored::control::{ShouldColorize, Stream};
+    ///
+    /// let colorize_control = ShouldColorize::from_sources(
+    ///     |name| match name {
+    ///         "NO_COLOR" => Some("1".to_string()),
+    ///         _ => None,
+    ///     },
+    ///     |_| true,
+    /// );
+    /// assert!(!colorize_control.for_stream(Stream::Stderr));
+    /// ```
+    pub fn from_sources<E, T>(env: E, is_tty: T) -> Self
+    where
+        E: Fn(&str) -> Option<String>,
+        T: Fn(Stream) -> bool,
+    {
+        let colorize_control = ShouldColorize::default();
+        colorize_control.refresh_from_sources(env, is_tty);
+        colorize_control
+    }
+
+    /// Read the environment variables and check if stdout and stderr are
+    /// ttys again, as in [`from_env`](ShouldColorize::from_env).
+    ///
+    /// The overrides and [`set_ci_detection`](ShouldColorize::set_ci_detection)
+    /// are kept. The other threads see the decision of the previous
+    /// environment or of the new one, never a mix of both.
+    pub fn refresh(&self) {
+        self.refresh_from_sources(
+            |name| env::var_os(name).map(|value| value.to_string_lossy().into_owned()),
+            |stream| match stream {
+                Stream::Stdout => io::stdout().is_terminal(),
+                Stream::Stderr => io::stderr().is_terminal(),
+            },
+        )
+    }
+
+    /// Like [`refresh`](ShouldColorize::refresh), with the sources of
+    /// [`from_sources`](ShouldColorize::from_sources).
+    pub fn refresh_from_sources<E, T>(&self, env: E, is_tty: T)
+    where
+        E: Fn(&str) -> Option<String>,
+        T: Fn(Stream) -> bool,
+    {
+        let var = |name: &str| env(name).ok_or(env::VarError::NotPresent);
+        let force_color = ShouldColorize::normalize_force_color(var("FORCE_COLOR"));
+        let dumb_term = var("TERM").is_ok_and(|term| term == "dumb");
+
+        let clicolor = ShouldColorize::normalize_env(var("CLICOLOR")).unwrap_or(true) && !dumb_term;
+        let stdout_is_tty = is_tty(Stream::Stdout);
+        let stderr_is_tty = is_tty(Stream::Stderr);
+        let clicolor_force = ShouldColorize::resolve_force(
+            var("NO_COLOR"),
+            var("CLICOLOR_FORCE"),
+            force_color,
+        );
+        let ci = ShouldColorize::detect_ci(|name| env(name).is_some());
+        #[cfg(feature = "terminfo")]
+        let terminfo = env("TERM")
+            .and_then(|term| ::terminfo::Terminfo::load_with(&term, &env))
+            .and_then(|terminfo| terminfo.color_level());
+
+        self.write_env(|| {
+            self.clicolor.store(clicolor, Ordering::Relaxed);
+            self.stdout_is_tty.store(stdout_is_tty, Ordering::Relaxed);
+            self.stderr_is_tty.store(stderr_is_tty, Ordering::Relaxed);
+            self.clicolor_force.store(clicolor_force);
+            self.color_level.store(force_color.and_then(|level| level));
+            self.ci.store(ci);
+            #[cfg(feature = "terminfo")]
+            self.terminfo.store(terminfo);
+        });
+    }
+
+    /// Returns if the current coloring is expected, for output written to
+    /// stdout.
     pub fn should_colorize(&self) -> bool {
+        self.for_stream(Stream::Stdout)
+    }
+
+    /// Returns if the current coloring is expected for output written to
+    /// `stream`. Only the tty check differs from one stream to the other.
+    ///
+    /// ```rust
+    /// use colored::control::{Stream, SHOULD_COLORIZE};
+    ///
+    /// if SHOULD_COLORIZE.for_stream(Stream::Stderr) {
+    ///     eprintln!("stderr is a terminal");
+    /// }
+    /// ```
+    pub fn for_stream(&self, stream: Stream) -> bool {
+        self.read_env(|| {
+            let is_tty = match stream {
+                Stream::Stdout => &self.stdout_is_tty,
+                Stream::Stderr => &self.stderr_is_tty,
+            }
+            .load(Ordering::Relaxed);
+            self.for_terminal(is_tty || self.ci_level().is_some())
+        })
+    }
+
+    /// Returns how many colors output written to `stream` supports, or `None`
+    /// if it shouldn't be colored at all.
+    ///
+    /// The level comes from `FORCE_COLOR`, or from the CI service the program
+    /// runs on, or with the `terminfo` feature from the terminfo entry of
+    /// `$TERM`, and is [`ColorLevel::Basic`] when none of them tells.
+    pub fn color_level(&self, stream: Stream) -> Option<ColorLevel> {
+        self.read_env(|| {
+            if self.for_stream(stream) {
+                let level = self
+                    .color_level
+                    .load()
+                    .or_else(|| self.ci_level())
+                    .or_else(|| self.terminfo_level());
+                Some(level.unwrap_or(ColorLevel::Basic))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns if the current coloring is expected for output written to
+    /// `writer`, such as a `File` or a `TcpStream`, depending on whether it
+    /// is a terminal.
+    ///
+    /// ```no_run
+    /// use colored::control::SHOULD_COLORIZE;
+    ///
+    /// let log = std::fs::File::create(std::env::temp_dir().join("colored.log")).unwrap();
+    /// if SHOULD_COLORIZE.for_writer(&log) {
+    ///     println!("the environment forces the colors");
+    /// }
+    /// ```
+    pub fn for_writer<W: IsTerminal>(&self, writer: &W) -> bool {
+        let is_terminal = writer.is_terminal();
+        self.read_env(|| self.for_terminal(is_terminal))
+    }
+
+    fn for_terminal(&self, is_terminal: bool) -> bool {
+        if let Some(thread_override) = THREAD_OVERRIDE.with(Cell::get) {
+            return thread_override;
+        }
+
         if self.has_manual_override.load(Ordering::Relaxed) {
             return self.manual_override.load(Ordering::Relaxed);
         }
 
-        if let Some(forced_value) = self.clicolor_force {
+        if let Some(forced_value) = self.clicolor_force.load() {
             return forced_value;
         }
 
-        self.clicolor
+        self.clicolor.load(Ordering::Relaxed) && is_terminal
     }
 
     /// Use this to force colored to ignore the environment and always/never colorize
@@ -139,8 +632,141 @@ impl ShouldColorize {
         self.has_manual_override.store(false, Ordering::Relaxed);
     }
 
+    /// Enable or disable treating stdout and stderr as terminals when running
+    /// on a known CI service. `NO_COLOR`, `CLICOLOR` and the overrides apply
+    /// either way.
+    pub fn set_ci_detection(&self, enabled: bool) {
+        self.ci_detection.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Set the override until the returned guard is dropped, then go back to
+    /// the previous one.
+    ///
+    /// Guards can be nested, and dropped in any order: the override of the
+    /// most recent guard still alive applies.
+    pub fn override_scope(&self, override_colorize: bool) -> OverrideGuard<'_> {
+        let mut scopes = self.lock_scopes();
+        let id = scopes.next_id;
+        scopes.next_id += 1;
+        scopes.stack.push(Scope {
+            id,
+            previous: self.manual_override(),
+        });
+        self.set_override(override_colorize);
+
+        OverrideGuard {
+            should_colorize: self,
+            id,
+        }
+    }
+
     /* private */
 
+    /// Run `read`, which loads the fields read from the environment, until
+    /// no refresh wrote them meanwhile.
+    fn read_env<F: Fn() -> R, R>(&self, read: F) -> R {
+        loop {
+            let sequence = self.sequence.load(Ordering::Acquire);
+            if sequence & 1 == 0 {
+                let value = read();
+                atomic::fence(Ordering::Acquire);
+                if self.sequence.load(Ordering::Relaxed) == sequence {
+                    return value;
+                }
+            }
+            hint::spin_loop();
+        }
+    }
+
+    /// Run `write`, which stores the fields read from the environment, with
+    /// the readers and the other refreshes kept out.
+    fn write_env<F: FnOnce()>(&self, write: F) {
+        let mut sequence = self.sequence.load(Ordering::Relaxed);
+        loop {
+            if sequence & 1 == 0 {
+                match self.sequence.compare_exchange_weak(
+                    sequence,
+                    sequence + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(current) => sequence = current,
+                }
+            } else {
+                hint::spin_loop();
+                sequence = self.sequence.load(Ordering::Relaxed);
+            }
+        }
+        atomic::fence(Ordering::Release);
+        write();
+        self.sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+    }
+
+    fn ci_level(&self) -> Option<ColorLevel> {
+        if self.ci_detection.load(Ordering::Relaxed) {
+            self.ci.load()
+        } else {
+            None
+        }
+    }
+
+    #[cfg(feature = "terminfo")]
+    fn terminfo_level(&self) -> Option<ColorLevel> {
+        self.terminfo.load()
+    }
+
+    #[cfg(not(feature = "terminfo"))]
+    fn terminfo_level(&self) -> Option<ColorLevel> {
+        None
+    }
+
+    /// The color level of the CI service the program runs on, if it is one
+    /// whose logs render colors.
+    fn detect_ci<F: Fn(&str) -> bool>(is_set: F) -> Option<ColorLevel> {
+        const TRUECOLOR_CI: [&str; 2] = ["GITHUB_ACTIONS", "GITEA_ACTIONS"];
+        const BASIC_CI: [&str; 7] = [
+            "GITLAB_CI",
+            "BUILDKITE",
+            "CIRCLECI",
+            "TRAVIS",
+            "APPVEYOR",
+            "DRONE",
+            "TF_BUILD",
+        ];
+
+        if TRUECOLOR_CI.iter().any(|&name| is_set(name)) {
+            Some(ColorLevel::TrueColor)
+        } else if BASIC_CI.iter().any(|&name| is_set(name)) {
+            Some(ColorLevel::Basic)
+        } else {
+            None
+        }
+    }
+
+    fn manual_override(&self) -> Option<bool> {
+        if self.has_manual_override.load(Ordering::Relaxed) {
+            Some(self.manual_override.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+
+    fn restore_override(&self, manual_override: Option<bool>) {
+        match manual_override {
+            Some(override_colorize) => self.set_override(override_colorize),
+            None => self.unset_override(),
+        }
+    }
+
+    fn lock_scopes(&self) -> MutexGuard<'_, Scopes> {
+        // the stack is always left consistent, even by a panicking thread
+        self.scopes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
     fn normalize_env(env_res: Result<String, env::VarError>) -> Option<bool> {
         match env_res {
             Ok(string) => Some(string != "0"),
@@ -148,13 +774,42 @@ impl ShouldColorize {
         }
     }
 
+    /// `None` if `FORCE_COLOR` isn't set, `Some(None)` if it disables the
+    /// colors, or the level it forces.
+    fn normalize_force_color(env_res: Result<String, env::VarError>) -> Option<Option<ColorLevel>> {
+        let value = env_res.ok()?;
+        let level = match value.trim() {
+            "0" | "false" => None,
+            "" | "1" | "true" => Some(ColorLevel::Basic),
+            "2" => Some(ColorLevel::Ansi256),
+            "3" => Some(ColorLevel::TrueColor),
+            other => match other.parse::<u32>() {
+                Ok(level) if level > 3 => Some(ColorLevel::TrueColor),
+                _ => Some(ColorLevel::Basic),
+            },
+        };
+        Some(level)
+    }
+
+    fn resolve_force(
+        no_color: Result<String, env::VarError>,
+        clicolor_force: Result<String, env::VarError>,
+        force_color: Option<Option<ColorLevel>>,
+    ) -> Option<bool> {
+        let clicolor_forced = ShouldColorize::normalize_env(clicolor_force.clone()) == Some(true);
+        match force_color {
+            Some(level) if !clicolor_forced => Some(level.is_some()),
+            _ => ShouldColorize::resolve_clicolor_force(no_color, clicolor_force),
+        }
+    }
+
     fn resolve_clicolor_force(
         no_color: Result<String, env::VarError>,
         clicolor_force: Result<String, env::VarError>,
     ) -> Option<bool> {
         if ShouldColorize::normalize_env(clicolor_force) == Some(true) {
             Some(true)
-        } else if ShouldColorize::normalize_env(no_color).is_some() {
+        } else if no_color.is_ok_and(|no_color| !no_color.is_empty()) {
             Some(false)
         } else {
             None
@@ -162,6 +817,24 @@ impl ShouldColorize {
     }
 }
 
+impl<'a> Drop for OverrideGuard<'a> {
+    fn drop(&mut self) {
+        let mut scopes = self.should_colorize.lock_scopes();
+        let idx = match scopes.stack.iter().position(|scope| scope.id == self.id) {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        let scope = scopes.stack.remove(idx);
+        match scopes.stack.get_mut(idx) {
+            // a more recent guard is still alive: it now goes back to what
+            // this one would have restored
+            Some(next) => next.previous = scope.previous,
+            None => self.should_colorize.restore_override(scope.previous),
+        }
+    }
+}
+
 #[cfg(test)]
 mod specs {
     use super::*;
@@ -245,6 +918,16 @@ mod specs {
                     },
                 );
 
+                ctx.it("should ignore an empty NO_COLOR", || {
+                    assert_eq!(
+                        None,
+                        ShouldColorize::resolve_clicolor_force(
+                            Ok(String::new()),
+                            Err(env::VarError::NotPresent)
+                        )
+                    );
+                });
+
                 ctx.it(
                     "should prioritize CLICOLOR_FORCE over NO_COLOR if CLICOLOR_FORCE is set to non-zero value",
                     || {
@@ -273,6 +956,356 @@ mod specs {
                 );
             });
 
+            ctx.describe("::normalize_force_color", |ctx| {
+                ctx.it("should return None if not set", || {
+                    assert_eq!(
+                        None,
+                        ShouldColorize::normalize_force_color(Err(env::VarError::NotPresent))
+                    );
+                });
+
+                ctx.it("should disable the colors if 0 or false", || {
+                    assert_eq!(
+                        Some(None),
+                        ShouldColorize::normalize_force_color(Ok(String::from("0")))
+                    );
+                    assert_eq!(
+                        Some(None),
+                        ShouldColorize::normalize_force_color(Ok(String::from("false")))
+                    );
+                });
+
+                ctx.it("should map 1, 2 and 3 to the color levels", || {
+                    let level = |value: &str| {
+                        ShouldColorize::normalize_force_color(Ok(String::from(value)))
+                    };
+                    assert_eq!(Some(Some(ColorLevel::Basic)), level("1"));
+                    assert_eq!(Some(Some(ColorLevel::Ansi256)), level("2"));
+                    assert_eq!(Some(Some(ColorLevel::TrueColor)), level("3"));
+                });
+
+                ctx.it("should force basic colors if empty or true", || {
+                    let level = |value: &str| {
+                        ShouldColorize::normalize_force_color(Ok(String::from(value)))
+                    };
+                    assert_eq!(Some(Some(ColorLevel::Basic)), level(""));
+                    assert_eq!(Some(Some(ColorLevel::Basic)), level("true"));
+                    assert_eq!(Some(Some(ColorLevel::Basic)), level("yes"));
+                    assert_eq!(Some(Some(ColorLevel::TrueColor)), level("16"));
+                });
+            });
+
+            ctx.describe("::resolve_force", |ctx| {
+                ctx.it("should prioritize CLICOLOR_FORCE over FORCE_COLOR", || {
+                    assert_eq!(
+                        Some(true),
+                        ShouldColorize::resolve_force(
+                            Err(env::VarError::NotPresent),
+                            Ok(String::from("1")),
+                            Some(None),
+                        )
+                    );
+                });
+
+                ctx.it("should prioritize FORCE_COLOR over NO_COLOR", || {
+                    assert_eq!(
+                        Some(true),
+                        ShouldColorize::resolve_force(
+                            Ok(String::from("1")),
+                            Err(env::VarError::NotPresent),
+                            Some(Some(ColorLevel::Ansi256)),
+                        )
+                    );
+                    assert_eq!(
+                        Some(false),
+                        ShouldColorize::resolve_force(
+                            Err(env::VarError::NotPresent),
+                            Ok(String::from("0")),
+                            Some(None),
+                        )
+                    );
+                });
+
+                ctx.it("should fall back to NO_COLOR and CLICOLOR_FORCE without FORCE_COLOR", || {
+                    assert_eq!(
+                        Some(false),
+                        ShouldColorize::resolve_force(
+                            Ok(String::from("1")),
+                            Err(env::VarError::NotPresent),
+                            None,
+                        )
+                    );
+                    assert_eq!(
+                        None,
+                        ShouldColorize::resolve_force(
+                            Err(env::VarError::NotPresent),
+                            Err(env::VarError::NotPresent),
+                            None,
+                        )
+                    );
+                });
+            });
+
+            ctx.describe("::color_level", |ctx| {
+                ctx.it("should be None without colors", || {
+                    let colorize_control = ShouldColorize {
+                        clicolor_force: AtomicOption::new(Some(false)),
+                        color_level: AtomicOption::new(Some(ColorLevel::TrueColor)),
+                        ..ShouldColorize::default()
+                    };
+                    assert_eq!(None, colorize_control.color_level(Stream::Stdout));
+                });
+
+                ctx.it("should default to basic colors", || {
+                    let colorize_control = ShouldColorize::default();
+                    assert_eq!(
+                        Some(ColorLevel::Basic),
+                        colorize_control.color_level(Stream::Stderr)
+                    );
+                });
+
+                ctx.it("should use the forced level", || {
+                    let colorize_control = ShouldColorize {
+                        clicolor: AtomicBool::new(false),
+                        clicolor_force: AtomicOption::new(Some(true)),
+                        color_level: AtomicOption::new(Some(ColorLevel::Ansi256)),
+                        ..ShouldColorize::default()
+                    };
+                    assert_eq!(
+                        Some(ColorLevel::Ansi256),
+                        colorize_control.color_level(Stream::Stdout)
+                    );
+                });
+            });
+
+            ctx.describe("::detect_ci", |ctx| {
+                ctx.it("should give true colors to GitHub Actions", || {
+                    assert_eq!(
+                        Some(ColorLevel::TrueColor),
+                        ShouldColorize::detect_ci(|name| name == "GITHUB_ACTIONS")
+                    );
+                });
+
+                ctx.it("should give basic colors to other CI services", || {
+                    assert_eq!(
+                        Some(ColorLevel::Basic),
+                        ShouldColorize::detect_ci(|name| name == "GITLAB_CI")
+                    );
+                    assert_eq!(
+                        Some(ColorLevel::Basic),
+                        ShouldColorize::detect_ci(|name| name == "BUILDKITE")
+                    );
+                });
+
+                ctx.it("should return None outside CI", || {
+                    assert_eq!(None, ShouldColorize::detect_ci(|_| false));
+                    assert_eq!(None, ShouldColorize::detect_ci(|name| name == "CI"));
+                });
+            });
+
+            ctx.describe("on a CI service", |ctx| {
+                ctx.it("should colorize streams which aren't ttys", || {
+                    let colorize_control = ShouldColorize {
+                        stdout_is_tty: AtomicBool::new(false),
+                        stderr_is_tty: AtomicBool::new(false),
+                        ci: AtomicOption::new(Some(ColorLevel::TrueColor)),
+                        ..ShouldColorize::default()
+                    };
+                    assert!(colorize_control.for_stream(Stream::Stdout));
+                    assert_eq!(
+                        Some(ColorLevel::TrueColor),
+                        colorize_control.color_level(Stream::Stderr)
+                    );
+                });
+
+                ctx.it("should still honor NO_COLOR and CLICOLOR", || {
+                    let colorize_control = ShouldColorize {
+                        stdout_is_tty: AtomicBool::new(false),
+                        clicolor_force: AtomicOption::new(Some(false)),
+                        ci: AtomicOption::new(Some(ColorLevel::Basic)),
+                        ..ShouldColorize::default()
+                    };
+                    assert!(!colorize_control.should_colorize());
+
+                    let colorize_control = ShouldColorize {
+                        clicolor: AtomicBool::new(false),
+                        stdout_is_tty: AtomicBool::new(false),
+                        ci: AtomicOption::new(Some(ColorLevel::Basic)),
+                        ..ShouldColorize::default()
+                    };
+                    assert!(!colorize_control.should_colorize());
+                });
+
+                ctx.it("should be disabled with set_ci_detection", || {
+                    let colorize_control = ShouldColorize {
+                        stdout_is_tty: AtomicBool::new(false),
+                        ci: AtomicOption::new(Some(ColorLevel::Basic)),
+                        ..ShouldColorize::default()
+                    };
+                    colorize_control.set_ci_detection(false);
+                    assert!(!colorize_control.should_colorize());
+                    colorize_control.set_ci_detection(true);
+                    assert!(colorize_control.should_colorize());
+                });
+
+                ctx.it("shouldn't apply to other writers", || {
+                    let name = format!("colored-ci-spec-{}", ::std::process::id());
+                    let path = env::temp_dir().join(name);
+                    let file = ::std::fs::File::create(&path).unwrap();
+                    let colorize_control = ShouldColorize {
+                        ci: AtomicOption::new(Some(ColorLevel::Basic)),
+                        ..ShouldColorize::default()
+                    };
+                    assert!(!colorize_control.for_writer(&file));
+
+                    drop(file);
+                    ::std::fs::remove_file(&path).unwrap();
+                });
+            });
+
+            ctx.describe("::from_env", |ctx| {
+                ctx.it("should follow the precedence table", || {
+                    // the variables, whether stdout is a tty, and the expected result
+                    type Row = (&'static [(&'static str, &'static str)], bool, bool);
+                    let table: &[Row] = &[
+                        (&[], true, true),
+                        (&[], false, false),
+                        (&[("CLICOLOR_FORCE", "1"), ("FORCE_COLOR", "0")], false, true),
+                        (&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")], false, true),
+                        (&[("CLICOLOR_FORCE", "0")], false, false),
+                        (&[("FORCE_COLOR", "1"), ("NO_COLOR", "1")], false, true),
+                        (&[("FORCE_COLOR", "0")], true, false),
+                        (&[("FORCE_COLOR", "1"), ("TERM", "dumb")], false, true),
+                        (&[("NO_COLOR", "1")], true, false),
+                        (&[("NO_COLOR", "0")], true, false),
+                        (&[("NO_COLOR", "")], true, true),
+                        (&[("NO_COLOR", "1"), ("GITHUB_ACTIONS", "true")], false, false),
+                        (&[("TERM", "dumb")], true, false),
+                        (&[("TERM", "dumb"), ("GITHUB_ACTIONS", "true")], false, false),
+                        (&[("TERM", "xterm-256color")], true, true),
+                        (&[("CLICOLOR", "0")], true, false),
+                        (&[("CLICOLOR", "0"), ("GITLAB_CI", "true")], false, false),
+                        (&[("CLICOLOR", "1")], false, false),
+                        (&[("GITLAB_CI", "true")], false, true),
+                    ];
+
+                    for &(vars, is_tty, expected) in table {
+                        let colorize_control = ShouldColorize::from_sources(
+                            |name| {
+                                vars.iter()
+                                    .find(|&&(var, _)| var == name)
+                                    .map(|&(_, value)| value.to_string())
+                            },
+                            |_| is_tty,
+                        );
+                        assert_eq!(
+                            expected,
+                            colorize_control.should_colorize(),
+                            "{:?} with a tty: {}",
+                            vars,
+                            is_tty
+                        );
+                    }
+                });
+            });
+
+            ctx.describe("::from_sources", |ctx| {
+                ctx.it("should probe each stream", || {
+                    let colorize_control =
+                        ShouldColorize::from_sources(|_| None, |stream| stream == Stream::Stderr);
+                    assert!(!colorize_control.for_stream(Stream::Stdout));
+                    assert!(colorize_control.for_stream(Stream::Stderr));
+                });
+
+                ctx.it("should only look up the given variables", || {
+                    let colorize_control = ShouldColorize::from_sources(
+                        |name| {
+                            if name == "FORCE_COLOR" {
+                                Some("3".to_string())
+                            } else {
+                                None
+                            }
+                        },
+                        |_| false,
+                    );
+                    assert_eq!(
+                        Some(ColorLevel::TrueColor),
+                        colorize_control.color_level(Stream::Stdout)
+                    );
+                });
+            });
+
+            ctx.describe("::refresh_from_sources", |ctx| {
+                ctx.it("should change the decision", || {
+                    let colorize_control = ShouldColorize::from_sources(|_| None, |_| true);
+                    assert!(colorize_control.should_colorize());
+
+                    colorize_control.refresh_from_sources(
+                        |name| {
+                            if name == "NO_COLOR" {
+                                Some("1".to_string())
+                            } else {
+                                None
+                            }
+                        },
+                        |_| true,
+                    );
+                    assert!(!colorize_control.should_colorize());
+
+                    colorize_control.refresh_from_sources(|_| None, |_| false);
+                    assert!(!colorize_control.should_colorize());
+                    colorize_control
+                        .refresh_from_sources(|_| None, |stream| stream == Stream::Stdout);
+                    assert!(colorize_control.should_colorize());
+                    assert!(!colorize_control.for_stream(Stream::Stderr));
+                });
+
+                ctx.it("should never mix two environments", || {
+                    use std::sync::Arc;
+                    use std::thread;
+
+                    let env = |name: &'static str, value: &'static str| {
+                        move |var: &str| if var == name { Some(value.to_string()) } else { None }
+                    };
+                    let colorize_control =
+                        Arc::new(ShouldColorize::from_sources(env("NO_COLOR", "1"), |_| false));
+                    let refreshing = Arc::clone(&colorize_control);
+                    let refresher = thread::spawn(move || {
+                        for _ in 0..1000 {
+                            refreshing.refresh_from_sources(env("FORCE_COLOR", "3"), |_| false);
+                            refreshing.refresh_from_sources(env("NO_COLOR", "1"), |_| false);
+                        }
+                    });
+
+                    // forcing the colors from one and the level from the
+                    // other would give `Some(ColorLevel::Basic)`
+                    while !refresher.is_finished() {
+                        let level = colorize_control.color_level(Stream::Stdout);
+                        assert!(level.is_none() || level == Some(ColorLevel::TrueColor));
+                    }
+                    refresher.join().unwrap();
+                });
+
+                ctx.it("should keep the overrides", || {
+                    let colorize_control = ShouldColorize::from_sources(|_| None, |_| false);
+                    colorize_control.set_override(true);
+                    colorize_control.set_ci_detection(false);
+                    colorize_control.refresh_from_sources(
+                        |name| {
+                            if name == "GITHUB_ACTIONS" {
+                                Some("true".to_string())
+                            } else {
+                                None
+                            }
+                        },
+                        |_| false,
+                    );
+                    assert!(colorize_control.should_colorize());
+                    colorize_control.unset_override();
+                    assert!(!colorize_control.should_colorize());
+                });
+            });
+
             ctx.describe("constructors", |ctx| {
                 ctx.it("should have a default constructor", || {
                     ShouldColorize::default();
@@ -286,22 +1319,84 @@ mod specs {
             ctx.describe("when only changing clicolors", |ctx| {
                 ctx.it("clicolor == false means no colors", || {
                     let colorize_control = ShouldColorize {
-                        clicolor: false,
+                        clicolor: AtomicBool::new(false),
                         ..ShouldColorize::default()
                     };
-                    false == colorize_control.should_colorize()
+                    !colorize_control.should_colorize()
                 });
 
                 ctx.it("clicolor == true means colors !", || {
                     let colorize_control = ShouldColorize {
-                        clicolor: true,
+                        clicolor: AtomicBool::new(true),
                         ..ShouldColorize::default()
                     };
-                    true == colorize_control.should_colorize()
+                    colorize_control.should_colorize()
                 });
 
                 ctx.it("unset clicolors implies true", || {
-                    true == ShouldColorize::default().should_colorize()
+                    ShouldColorize::default().should_colorize()
+                });
+            });
+
+            ctx.describe("::for_stream", |ctx| {
+                ctx.it("should use the tty check of each stream", || {
+                    let colorize_control = ShouldColorize {
+                        stdout_is_tty: AtomicBool::new(false),
+                        stderr_is_tty: AtomicBool::new(true),
+                        ..ShouldColorize::default()
+                    };
+
+                    assert!(!colorize_control.for_stream(Stream::Stdout));
+                    assert!(colorize_control.for_stream(Stream::Stderr));
+                    assert!(!colorize_control.should_colorize());
+                });
+
+                ctx.it("clicolor == false means no colors on either stream", || {
+                    let colorize_control = ShouldColorize {
+                        clicolor: AtomicBool::new(false),
+                        ..ShouldColorize::default()
+                    };
+
+                    assert!(!colorize_control.for_stream(Stream::Stdout));
+                    assert!(!colorize_control.for_stream(Stream::Stderr));
+                });
+
+                ctx.it("clicolor_force should apply to both streams", || {
+                    let colorize_control = ShouldColorize {
+                        stderr_is_tty: AtomicBool::new(false),
+                        clicolor_force: AtomicOption::new(Some(true)),
+                        ..ShouldColorize::default()
+                    };
+
+                    assert!(colorize_control.for_stream(Stream::Stdout));
+                    assert!(colorize_control.for_stream(Stream::Stderr));
+                });
+
+                ctx.it("a manual override should apply to both streams", || {
+                    let colorize_control = ShouldColorize::default();
+                    colorize_control.set_override(false);
+
+                    assert!(!colorize_control.for_stream(Stream::Stdout));
+                    assert!(!colorize_control.for_stream(Stream::Stderr));
+                });
+            });
+
+            ctx.describe("::for_writer", |ctx| {
+                ctx.it("files aren't terminals", || {
+                    let name = format!("colored-for-writer-spec-{}", ::std::process::id());
+                    let path = env::temp_dir().join(name);
+                    let file = ::std::fs::File::create(&path).unwrap();
+
+                    assert!(!ShouldColorize::default().for_writer(&file));
+
+                    let colorize_control = ShouldColorize {
+                        clicolor_force: AtomicOption::new(Some(true)),
+                        ..ShouldColorize::default()
+                    };
+                    assert!(colorize_control.for_writer(&file));
+
+                    drop(file);
+                    ::std::fs::remove_file(&path).unwrap();
                 });
             });
 
@@ -310,12 +1405,12 @@ mod specs {
                     "clicolor_force should force to true no matter clicolor",
                     || {
                         let colorize_control = ShouldColorize {
-                            clicolor: false,
-                            clicolor_force: Some(true),
+                            clicolor: AtomicBool::new(false),
+                            clicolor_force: AtomicOption::new(Some(true)),
                             ..ShouldColorize::default()
                         };
 
-                        true == colorize_control.should_colorize()
+                        colorize_control.should_colorize()
                     },
                 );
 
@@ -323,12 +1418,12 @@ mod specs {
                     "clicolor_force should force to false no matter clicolor",
                     || {
                         let colorize_control = ShouldColorize {
-                            clicolor: true,
-                            clicolor_force: Some(false),
+                            clicolor: AtomicBool::new(true),
+                            clicolor_force: AtomicOption::new(Some(false)),
                             ..ShouldColorize::default()
                         };
 
-                        false == colorize_control.should_colorize()
+                        !colorize_control.should_colorize()
                     },
                 );
             });
@@ -336,26 +1431,26 @@ mod specs {
             ctx.describe("using a manual override", |ctx| {
                 ctx.it("shoud colorize if manual_override is true, but clicolor is false and clicolor_force also false", || {
                     let colorize_control = ShouldColorize {
-                        clicolor: false,
-                        clicolor_force: None,
+                        clicolor: AtomicBool::new(false),
+                        clicolor_force: AtomicOption::new(None),
                         has_manual_override: AtomicBool::new(true),
                         manual_override: AtomicBool::new(true),
-                        .. ShouldColorize::default()
+                        ..ShouldColorize::default()
                     };
 
-                    true == colorize_control.should_colorize()
+                    colorize_control.should_colorize()
                 });
 
                 ctx.it("should not colorize if manual_override is false, but clicolor is true or clicolor_force is true", || {
                     let colorize_control = ShouldColorize {
-                        clicolor: true,
-                        clicolor_force: Some(true),
+                        clicolor: AtomicBool::new(true),
+                        clicolor_force: AtomicOption::new(Some(true)),
                         has_manual_override: AtomicBool::new(true),
                         manual_override: AtomicBool::new(false),
-                        .. ShouldColorize::default()
+                        ..ShouldColorize::default()
                     };
 
-                    false == colorize_control.should_colorize()
+                    !colorize_control.should_colorize()
                 })
             });
 
@@ -369,25 +1464,101 @@ mod specs {
                     let colorize_control = ShouldColorize::default();
                     colorize_control.set_override(true);
                     {
-                        assert_eq!(
-                            true,
-                            colorize_control.has_manual_override.load(Ordering::Relaxed)
-                        );
+                        assert!(colorize_control.has_manual_override.load(Ordering::Relaxed));
                         let val = colorize_control.manual_override.load(Ordering::Relaxed);
-                        assert_eq!(true, val);
+                        assert!(val);
                     }
                     colorize_control.set_override(false);
                     {
-                        assert_eq!(
-                            true,
-                            colorize_control.has_manual_override.load(Ordering::Relaxed)
-                        );
+                        assert!(colorize_control.has_manual_override.load(Ordering::Relaxed));
                         let val = colorize_control.manual_override.load(Ordering::Relaxed);
-                        assert_eq!(false, val);
+                        assert!(!val);
                     }
                 });
             });
 
+            ctx.describe("::override_scope", |ctx| {
+                ctx.it("should restore the environment when dropped", || {
+                    let colorize_control = ShouldColorize {
+                        clicolor: AtomicBool::new(false),
+                        ..ShouldColorize::default()
+                    };
+                    {
+                        let _guard = colorize_control.override_scope(true);
+                        assert!(colorize_control.should_colorize());
+                    }
+                    assert!(!colorize_control.should_colorize());
+                    assert!(!colorize_control.has_manual_override.load(Ordering::Relaxed));
+                });
+
+                ctx.it("should restore a previous override", || {
+                    let colorize_control = ShouldColorize::default();
+                    colorize_control.set_override(false);
+                    drop(colorize_control.override_scope(true));
+                    assert_eq!(Some(false), colorize_control.manual_override());
+                });
+
+                ctx.it("should restore nested guards in order", || {
+                    let colorize_control = ShouldColorize::default();
+                    let outer = colorize_control.override_scope(false);
+                    let inner = colorize_control.override_scope(true);
+                    assert!(colorize_control.should_colorize());
+                    drop(inner);
+                    assert!(!colorize_control.should_colorize());
+                    drop(outer);
+                    assert_eq!(None, colorize_control.manual_override());
+                });
+
+                ctx.it("should keep the latest override when dropped out of order", || {
+                    let colorize_control = ShouldColorize::default();
+                    colorize_control.set_override(true);
+                    let first = colorize_control.override_scope(false);
+                    let second = colorize_control.override_scope(true);
+                    let third = colorize_control.override_scope(false);
+
+                    drop(second);
+                    assert_eq!(Some(false), colorize_control.manual_override());
+                    drop(first);
+                    assert_eq!(Some(false), colorize_control.manual_override());
+                    drop(third);
+                    assert_eq!(Some(true), colorize_control.manual_override());
+                });
+            });
+
+            ctx.describe("::with_colors", |ctx| {
+                ctx.it("should come before the manual override", || {
+                    let colorize_control = ShouldColorize::default();
+                    colorize_control.set_override(true);
+
+                    assert!(!with_colors(false, || colorize_control.should_colorize()));
+                    assert!(with_colors(true, || colorize_control.for_stream(Stream::Stderr)));
+                    assert!(colorize_control.should_colorize());
+                });
+
+                ctx.it("should only apply to the current thread", || {
+                    let colorize_control = ShouldColorize::default();
+                    with_colors(false, || {
+                        ::std::thread::scope(|scope| {
+                            let other = scope.spawn(|| colorize_control.should_colorize());
+                            assert!(other.join().unwrap());
+                        });
+                        assert!(!colorize_control.should_colorize());
+                    });
+                });
+
+                ctx.it("should restore the previous thread override", || {
+                    let colorize_control = ShouldColorize {
+                        clicolor: AtomicBool::new(false),
+                        ..ShouldColorize::default()
+                    };
+                    with_colors(true, || {
+                        with_colors(false, || assert!(!colorize_control.should_colorize()));
+                        assert!(colorize_control.should_colorize());
+                    });
+                    assert!(!colorize_control.should_colorize());
+                });
+            });
+
             ctx.describe("::unset_override", |ctx| {
                 ctx.it("should exists", || {
                     let colorize_control = ShouldColorize::default();
@@ -398,12 +1569,46 @@ mod specs {
                     let colorize_control = ShouldColorize::default();
                     colorize_control.set_override(true);
                     colorize_control.unset_override();
+                    assert!(!colorize_control.has_manual_override.load(Ordering::Relaxed));
+                });
+            });
+        });
+        runner.add_event_handler(&mut formatter);
+        runner.run().unwrap();
+    }
+
+    #[test]
+    fn color_choice() {
+        use std::io;
+
+        let stdout = &mut io::stdout();
+        let mut formatter = rspec::formatter::Simple::new(stdout);
+        let mut runner = describe("ColorChoice", |ctx| {
+            ctx.describe("::from_str", |ctx| {
+                ctx.it("should parse the choices", || {
+                    assert_eq!(Ok(ColorChoice::Auto), "auto".parse());
+                    assert_eq!(Ok(ColorChoice::Always), "always".parse());
+                    assert_eq!(Ok(ColorChoice::Never), "Never".parse());
+                });
+
+                ctx.it("should reject anything else", || {
+                    let err = "sometimes".parse::<ColorChoice>().unwrap_err();
                     assert_eq!(
-                        false,
-                        colorize_control.has_manual_override.load(Ordering::Relaxed)
+                        "unknown color choice `sometimes`, expected `auto`, `always` or `never`",
+                        err.to_string()
                     );
                 });
             });
+
+            ctx.it("should display as it parses", || {
+                for &choice in &[ColorChoice::Auto, ColorChoice::Always, ColorChoice::Never] {
+                    assert_eq!(Ok(choice), choice.to_string().parse());
+                }
+            });
+
+            ctx.it("should default to auto", || {
+                assert_eq!(ColorChoice::Auto, ColorChoice::default());
+            });
         });
         runner.add_event_handler(&mut formatter);
         runner.run().unwrap();
@@ -0,0 +1,103 @@
+//! A couple of functions to enable and disable coloring.
+//!
+//! Without the `std` feature, there is no environment nor terminal to check:
+//! nothing is colored until [`set_override`] enables the colors.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// An output stream, which may go to a terminal while the other one doesn't.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stream {
+    /// The standard output.
+    Stdout,
+    /// The standard error.
+    Stderr,
+}
+
+/// A flag to to if coloring should occur.
+pub struct ShouldColorize {
+    has_manual_override: AtomicBool,
+    manual_override: AtomicBool,
+}
+
+/// The persistent [`ShouldColorize`].
+pub static SHOULD_COLORIZE: ShouldColorize = ShouldColorize::new();
+
+/// Use this to force colored to ignore the environment and always/never colorize
+pub fn set_override(override_colorize: bool) {
+    SHOULD_COLORIZE.set_override(override_colorize)
+}
+
+/// Remove the manual override, which disables the colors
+pub fn unset_override() {
+    SHOULD_COLORIZE.unset_override()
+}
+
+/// Run `f` with colors forced on or off, then go back to the previous
+/// override.
+///
+/// Without the `std` feature there are no threads to tell apart, so unlike
+/// with `std` the override applies to the whole program meanwhile.
+pub fn with_colors<F: FnOnce() -> R, R>(colorize: bool, f: F) -> R {
+    /// Restores the previous override, even if `f` panics.
+    struct Restore(Option<bool>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            match self.0 {
+                Some(previous) => set_override(previous),
+                None => unset_override(),
+            }
+        }
+    }
+
+    let previous = if SHOULD_COLORIZE.has_manual_override.load(Ordering::Relaxed) {
+        Some(SHOULD_COLORIZE.manual_override.load(Ordering::Relaxed))
+    } else {
+        None
+    };
+    let _restore = Restore(previous);
+    set_override(colorize);
+    f()
+}
+
+impl Default for ShouldColorize {
+    fn default() -> ShouldColorize {
+        ShouldColorize::new()
+    }
+}
+
+impl ShouldColorize {
+    /// A flag without override, so which doesn't colorize.
+    pub const fn new() -> ShouldColorize {
+        ShouldColorize {
+            has_manual_override: AtomicBool::new(false),
+            manual_override: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns if the current coloring is expected, that is if the override
+    /// enables the colors.
+    pub fn should_colorize(&self) -> bool {
+        self.has_manual_override.load(Ordering::Relaxed)
+            && self.manual_override.load(Ordering::Relaxed)
+    }
+
+    /// Returns if the current coloring is expected for output written to
+    /// `stream`, the same for both of them.
+    pub fn for_stream(&self, _stream: Stream) -> bool {
+        self.should_colorize()
+    }
+
+    /// Use this to force colored to ignore the environment and always/never colorize
+    pub fn set_override(&self, override_colorize: bool) {
+        self.has_manual_override.store(true, Ordering::Relaxed);
+        self.manual_override
+            .store(override_colorize, Ordering::Relaxed);
+    }
+
+    /// Remove the manual override, which disables the colors
+    pub fn unset_override(&self) {
+        self.has_manual_override.store(false, Ordering::Relaxed);
+    }
+}
@@ -1,15 +1,366 @@
+//! The output formats of colored strings.
+//!
+//! A [`ColoringFormatter`] writes a string with its colors and style: as ANSI
+//! escape sequences for terminals ([`Ansi`], the default), as plain text
+//! ([`NoColor`]) or as HTML ([`Html`]). Implement it to plug in another
+//! format.
+//!
+//! Choose one for a single string with [`ColoredString::format_with`], or
+//! for every colored string with [`set_formatter`].
+//!
+//! ```rust
+//! use colored::formatters::Html;
+//! use colored::*;
+//!
+//! // <span style="color:#cd0000;font-weight:bold">error</span>
+//! println!("{}", "error".red().bold().format_with(&Html));
+//! ```
+//!
+//! [`ColoredString::format_with`]: crate::ColoredString::format_with
 
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, RwLock,
+};
+
+use ansi::{self, Segment};
 use color::Color;
-use style::Style;
+use style::{Style, Styles};
 
+/// Writes a string with its colors and style.
 pub trait ColoringFormatter {
-    fn format(out: String, fg: Color, bg: Color, style: Style) -> String;
+    /// Write `input` to `out` with the given colors and style. The width and
+    /// precision of the format string are already applied to `input`.
+    fn format(
+        &self,
+        out: &mut dyn fmt::Write,
+        input: &str,
+        fgcolor: Option<Color>,
+        bgcolor: Option<Color>,
+        style: Style,
+    ) -> fmt::Result;
+}
+
+impl<F: ColoringFormatter + ?Sized> ColoringFormatter for &F {
+    fn format(
+        &self,
+        out: &mut dyn fmt::Write,
+        input: &str,
+        fgcolor: Option<Color>,
+        bgcolor: Option<Color>,
+        style: Style,
+    ) -> fmt::Result {
+        (**self).format(out, input, fgcolor, bgcolor, style)
+    }
+}
+
+/// ANSI escape sequences, for terminals.
+///
+/// The resets of colored strings nested in the input go back to the colors
+/// and style of the outer string.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Ansi;
+
+impl ColoringFormatter for Ansi {
+    fn format(
+        &self,
+        out: &mut dyn fmt::Write,
+        input: &str,
+        fgcolor: Option<Color>,
+        bgcolor: Option<Color>,
+        style: Style,
+    ) -> fmt::Result {
+        let mut style_str = String::new();
+        ::write_style(&mut style_str, fgcolor, bgcolor, style)?;
+        if style_str.is_empty() {
+            return out.write_str(input);
+        }
+
+        out.write_str(&style_str)?;
+        out.write_str(&::escape_inner_reset_sequences(input, &style_str))?;
+        out.write_str("\x1B[0m")
+    }
 }
 
+/// Plain text, without colors nor style.
+#[derive(Clone, Copy, Debug, Default)]
 pub struct NoColor;
 
 impl ColoringFormatter for NoColor {
-    fn format(out: String, _fg: Color, _bg: Color, _style: Style) -> String {
+    fn format(
+        &self,
+        out: &mut dyn fmt::Write,
+        input: &str,
+        _fgcolor: Option<Color>,
+        _bgcolor: Option<Color>,
+        _style: Style,
+    ) -> fmt::Result {
+        out.write_str(input)
+    }
+}
+
+/// HTML, with the colors and style in the `style` attribute of a `<span>`.
+///
+/// The input is escaped, and the escape sequences of nested colored strings
+/// are left out. The basic colors are the ones of xterm.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Html;
+
+impl Html {
+    fn write_color(out: &mut dyn fmt::Write, property: &str, color: Color) -> fmt::Result {
+        let (r, g, b) = match color {
+            Color::Black => (0x00, 0x00, 0x00),
+            Color::Red => (0xcd, 0x00, 0x00),
+            Color::Green => (0x00, 0xcd, 0x00),
+            Color::Yellow => (0xcd, 0xcd, 0x00),
+            Color::Blue => (0x00, 0x00, 0xee),
+            Color::Magenta => (0xcd, 0x00, 0xcd),
+            Color::Cyan => (0x00, 0xcd, 0xcd),
+            Color::White => (0xe5, 0xe5, 0xe5),
+            Color::BrightBlack => (0x7f, 0x7f, 0x7f),
+            Color::BrightRed => (0xff, 0x00, 0x00),
+            Color::BrightGreen => (0x00, 0xff, 0x00),
+            Color::BrightYellow => (0xff, 0xff, 0x00),
+            Color::BrightBlue => (0x5c, 0x5c, 0xff),
+            Color::BrightMagenta => (0xff, 0x00, 0xff),
+            Color::BrightCyan => (0x00, 0xff, 0xff),
+            Color::BrightWhite => (0xff, 0xff, 0xff),
+            Color::TrueColor { r, g, b } => (r, g, b),
+        };
+        write!(out, "{}:#{:02x}{:02x}{:02x}", property, r, g, b)
+    }
+
+    fn write_escaped(out: &mut dyn fmt::Write, input: &str) -> fmt::Result {
+        for segment in ansi::segments(input) {
+            if let Segment::Text(text) = segment {
+                let mut rest = text;
+                while let Some(idx) = rest.find(['&', '<', '>', '"', '\'']) {
+                    out.write_str(&rest[..idx])?;
+                    out.write_str(match rest.as_bytes()[idx] {
+                        b'&' => "&amp;",
+                        b'<' => "&lt;",
+                        b'>' => "&gt;",
+                        b'"' => "&quot;",
+                        _ => "&#39;",
+                    })?;
+                    rest = &rest[idx + 1..];
+                }
+                out.write_str(rest)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ColoringFormatter for Html {
+    fn format(
+        &self,
+        out: &mut dyn fmt::Write,
+        input: &str,
+        fgcolor: Option<Color>,
+        bgcolor: Option<Color>,
+        style: Style,
+    ) -> fmt::Result {
+        let (fgcolor, bgcolor) = if style.contains(Styles::Reversed) {
+            (bgcolor, fgcolor)
+        } else {
+            (fgcolor, bgcolor)
+        };
+
+        let mut properties: Vec<String> = Vec::new();
+        if let Some(color) = fgcolor {
+            let mut property = String::new();
+            Html::write_color(&mut property, "color", color)?;
+            properties.push(property);
+        }
+        if let Some(color) = bgcolor {
+            let mut property = String::new();
+            Html::write_color(&mut property, "background-color", color)?;
+            properties.push(property);
+        }
+        let mut decorations = Vec::new();
+        for style in style.iter() {
+            match style {
+                Styles::Bold => properties.push("font-weight:bold".into()),
+                Styles::Dimmed => properties.push("opacity:0.5".into()),
+                Styles::Italic => properties.push("font-style:italic".into()),
+                Styles::Hidden => properties.push("visibility:hidden".into()),
+                Styles::Underline => decorations.push("underline"),
+                Styles::Strikethrough => decorations.push("line-through"),
+                Styles::Blink => decorations.push("blink"),
+                Styles::Clear | Styles::Reversed => (),
+            }
+        }
+        if !decorations.is_empty() {
+            properties.push(format!("text-decoration:{}", decorations.join(" ")));
+        }
+
+        if properties.is_empty() {
+            return Html::write_escaped(out, input);
+        }
+        write!(out, "<span style=\"{}\">", properties.join(";"))?;
+        Html::write_escaped(out, input)?;
+        out.write_str("</span>")
+    }
+}
+
+#[cfg(feature = "std")]
+lazy_static! {
+    static ref FORMATTER: RwLock<Option<Arc<dyn ColoringFormatter + Send + Sync>>> =
+        RwLock::new(None);
+}
+
+/// Whether a formatter was set, so that displaying a string doesn't take the
+/// lock with the default one.
+#[cfg(feature = "std")]
+static FORMATTER_SET: AtomicBool = AtomicBool::new(false);
+
+/// Replace the formatter used to display every colored string, [`Ansi`] by
+/// default.
+///
+/// When colors shouldn't be written according to
+/// [`control`](crate::control), the formatter still writes the text, without
+/// colors nor style: set the override as well to always write them, e.g. into
+/// an HTML page.
+///
+/// The strings built by `cformat!` and the other macros of the `macros`
+/// feature already hold their escape sequences, so they aren't written
+/// through the formatter.
+///
+/// ```rust
+/// use colored::formatters::{self, Html};
+/// use colored::*;
+///
+/// formatters::set_formatter(Html);
+/// control::set_override(true);
+/// // <span style="font-weight:bold">x</span>
+/// println!("{}", "x".bold());
+/// ```
+#[cfg(feature = "std")]
+pub fn set_formatter<F: ColoringFormatter + Send + Sync + 'static>(formatter: F) {
+    *FORMATTER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(formatter));
+    FORMATTER_SET.store(true, Ordering::Release);
+}
+
+/// Go back to the [`Ansi`] formatter.
+#[cfg(feature = "std")]
+pub fn reset_formatter() {
+    FORMATTER_SET.store(false, Ordering::Release);
+    *FORMATTER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// The formatter set with [`set_formatter`], if any.
+#[cfg(feature = "std")]
+pub(crate) fn global() -> Option<Arc<dyn ColoringFormatter + Send + Sync>> {
+    if !FORMATTER_SET.load(Ordering::Acquire) {
+        return None;
+    }
+    FORMATTER.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// A [`ColoredString`] or [`ColoredStrings`] displayed with a given
+/// formatter, whether colors should be written or not.
+///
+/// Obtained with [`ColoredString::format_with`] or
+/// [`ColoredStrings::format_with`].
+///
+/// [`ColoredString`]: crate::ColoredString
+/// [`ColoredStrings`]: crate::ColoredStrings
+/// [`ColoredString::format_with`]: crate::ColoredString::format_with
+/// [`ColoredStrings::format_with`]: crate::ColoredStrings::format_with
+#[derive(Clone, Copy)]
+pub struct FormatWith<'a, T: 'a> {
+    pub(crate) value: &'a T,
+    pub(crate) formatter: &'a dyn ColoringFormatter,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use style::NO_STYLE;
+
+    fn format<F: ColoringFormatter>(
+        formatter: F,
+        input: &str,
+        fgcolor: Option<Color>,
+        bgcolor: Option<Color>,
+        style: Style,
+    ) -> String {
+        let mut out = String::new();
+        formatter
+            .format(&mut out, input, fgcolor, bgcolor, style)
+            .unwrap();
         out
     }
+
+    #[test]
+    fn ansi() {
+        assert_eq!(
+            "\x1B[1;44;31mx\x1B[0m",
+            format(
+                Ansi,
+                "x",
+                Some(Color::Red),
+                Some(Color::Blue),
+                Styles::Bold.into()
+            )
+        );
+        assert_eq!("x", format(Ansi, "x", None, None, NO_STYLE));
+        assert_eq!(
+            "\x1B[32ma\x1B[0m\x1B[32mb\x1B[0m",
+            format(Ansi, "a\x1B[0mb", Some(Color::Green), None, NO_STYLE)
+        );
+    }
+
+    #[test]
+    fn no_color() {
+        assert_eq!(
+            "x",
+            format(NoColor, "x", Some(Color::Red), None, Styles::Bold.into())
+        );
+    }
+
+    #[test]
+    fn html() {
+        assert_eq!(
+            r#"<span style="color:#0a80ff;background-color:#000000">x</span>"#,
+            format(
+                Html,
+                "x",
+                Some(Color::TrueColor {
+                    r: 10,
+                    g: 128,
+                    b: 255
+                }),
+                Some(Color::Black),
+                NO_STYLE
+            )
+        );
+        assert_eq!(
+            r#"<span style="font-style:italic;text-decoration:underline line-through">x</span>"#,
+            format(
+                Html,
+                "x",
+                None,
+                None,
+                Styles::Italic + Styles::Underline + Styles::Strikethrough
+            )
+        );
+        assert_eq!(
+            r#"<span style="background-color:#cd0000">x</span>"#,
+            format(Html, "x", Some(Color::Red), None, Styles::Reversed.into())
+        );
+    }
+
+    #[test]
+    fn html_escaping() {
+        assert_eq!(
+            "a &lt;b&gt; &amp; &quot;c&#39; d",
+            format(Html, "a <b> & \"c' \x1B[31md\x1B[0m", None, None, NO_STYLE)
+        );
+    }
 }
@@ -23,30 +23,92 @@
 //!
 //! See [the `Colorize` trait](./trait.Colorize.html) for all the methods.
 //!
+//! Without the default `std` feature, `colored` only needs `alloc`. Colors,
+//! styles and colored strings work the same, but there is no environment or
+//! terminal to check: the colors are only written once enabled with
+//! [`control::set_override`].
+//!
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(missing_docs)]
 
-extern crate atty;
+#[cfg_attr(not(any(feature = "std", test)), macro_use)]
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate core;
+#[cfg(feature = "clap")]
+extern crate clap;
+#[cfg(feature = "macros")]
+extern crate colored_macros;
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate lazy_static;
+#[cfg(all(unix, feature = "std"))]
+extern crate libc;
 extern crate paste;
+extern crate unicode_segmentation;
+extern crate unicode_width;
 #[cfg(windows)]
 extern crate winapi;
 
+#[cfg(feature = "serde")]
+extern crate serde;
+
 #[cfg(test)]
 extern crate rspec;
 
+pub mod ansi;
+#[cfg(feature = "std")]
+mod background;
 mod color;
+#[macro_use]
+mod painted;
+mod colored_str;
+mod colored_strings;
+pub mod formatters;
+// without `std`, only the override decides
+#[cfg_attr(not(feature = "std"), path = "control_no_std.rs")]
 pub mod control;
+#[cfg(feature = "std")]
+pub mod markup;
 mod style;
+#[cfg(feature = "terminfo")]
+pub mod terminfo;
+#[cfg(feature = "std")]
+pub mod theme;
+#[cfg(feature = "std")]
+mod writer;
 
 pub use color::*;
+pub use colored_str::ColoredStr;
+pub use colored_strings::ColoredStrings;
+pub use painted::{Paint, Painted};
 pub use style::{Style, Styles, NO_STYLE, ALL_STYLE};
-
-use std::{borrow::Cow, fmt, ops::Deref};
+#[cfg(feature = "std")]
+pub use writer::{write_to, ColorWriter};
+
+#[cfg(feature = "macros")]
+pub use colored_macros::{ceprint, ceprintln, cformat, cprint, cprintln};
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Write},
+    ops::Deref,
+};
 use paste::paste;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// A string that may have color and/or style applied to it.
+///
+/// With the `serde` feature, it serializes as its text along with its colors
+/// and styles, so the styling survives being sent elsewhere.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct ColoredString {
     input: String,
     fgcolor: Option<Color>,
@@ -179,6 +241,20 @@ pub trait Colorize {
     fn normal(self) -> ColoredString where Self: Sized {
         self.clear()
     }
+
+    /// Apply the style attached to `name` in the theme set with
+    /// [`theme::set_theme`]. Unknown names leave the colors and styles as they are.
+    #[cfg(feature = "std")]
+    fn themed(self, name: &str) -> ColoredString where Self: Sized {
+        theme::apply_global(name, self)
+    }
+
+    /// Apply the style attached to `name` in `theme`. Unknown names leave the
+    /// colors and styles as they are.
+    #[cfg(feature = "std")]
+    fn themed_with(self, name: &str, theme: &theme::Theme) -> ColoredString where Self: Sized {
+        theme.apply(name, self)
+    }
 }
 
 impl ColoredString {
@@ -234,82 +310,452 @@ impl ColoredString {
         self.bgcolor.is_none() && self.fgcolor.is_none() && self.style == NO_STYLE
     }
 
-    #[cfg(not(feature = "no-color"))]
-    fn has_colors(&self) -> bool {
-        control::SHOULD_COLORIZE.should_colorize()
+    /// Cut the string so that it takes at most `width` columns on a terminal,
+    /// ending it with `ellipsis` if anything was cut. See
+    /// [`ansi::truncate_visible`].
+    ///
+    /// Unlike the `{:.N}` precision, which cuts the string the same way, it
+    /// keeps the colors and style around the result.
+    ///
+    /// ```rust
+    /// # use colored::*;
+    /// let cstr = format!("error in {}", "main.rs".bold()).red();
+    /// let short = cstr.truncate_visible(10, "…");
+    /// assert_eq!(short.fgcolor(), Some(Color::Red));
+    /// assert_eq!(colored::ansi::visible_width(&short), 10);
+    /// ```
+    pub fn truncate_visible(&self, width: usize, ellipsis: &str) -> ColoredString {
+        ColoredString {
+            input: ansi::truncate_visible(&self.input, width, ellipsis).into_owned(),
+            ..self.clone()
+        }
     }
 
-    #[cfg(feature = "no-color")]
-    fn has_colors(&self) -> bool {
-        false
+    /// Render the string and wrap it on word boundaries so that no line takes
+    /// more than `width` columns. See [`ansi::wrap`].
+    ///
+    /// Every line closes the colors and style it uses, so the lines can be
+    /// printed on their own, e.g. after a prefix.
+    ///
+    /// ```rust
+    /// # use colored::*;
+    /// let cstr = "the quick brown fox jumps".green();
+    /// for line in cstr.wrap(10) {
+    ///     println!("> {}", line);
+    /// }
+    /// assert_eq!(cstr.wrap(10).len(), 3);
+    /// ```
+    pub fn wrap(&self, width: usize) -> Vec<String> {
+        ansi::wrap(&self.to_string(), width)
     }
 
-    fn compute_style(&self) -> String {
-        if !self.has_colors() || self.is_plain() {
-            return String::new();
+    /// Display the string with the colors enabled or not depending on
+    /// whether `stream` is a terminal, instead of stdout.
+    ///
+    /// ```rust
+    /// # use colored::*;
+    /// use colored::control::Stream;
+    ///
+    /// eprintln!("{}: oops", "error".red().for_stream(Stream::Stderr));
+    /// ```
+    pub fn for_stream(&self, stream: control::Stream) -> ForStream<'_> {
+        ForStream {
+            string: self,
+            stream,
         }
+    }
 
-        let mut res = String::from("\x1B[");
-        let mut has_wrote = if self.style != NO_STYLE {
-            res.push_str(&self.style.to_str());
-            true
-        } else {
-            false
-        };
-
-        if let Some(ref bgcolor) = self.bgcolor {
-            if has_wrote {
-                res.push(';');
-            }
+    /// Display the string through `formatter`, such as
+    /// [`formatters::Html`], instead of the formatter set with
+    /// [`formatters::set_formatter`]. The colors are written whatever
+    /// [`control`] decides, unless the `no-color` feature is enabled.
+    ///
+    /// ```rust
+    /// # use colored::*;
+    /// use colored::formatters::Html;
+    ///
+    /// // <td><span style="color:#00cd00">  ok</span></td>
+    /// println!("<td>{:>4}</td>", "ok".green().format_with(&Html));
+    /// ```
+    pub fn format_with<'a>(
+        &'a self,
+        formatter: &'a dyn formatters::ColoringFormatter,
+    ) -> formatters::FormatWith<'a, ColoredString> {
+        formatters::FormatWith {
+            value: self,
+            formatter,
+        }
+    }
 
-            res.push_str(&bgcolor.to_bg_str());
-            has_wrote = true;
+    fn attributes(&self) -> Attributes {
+        Attributes {
+            fgcolor: self.fgcolor,
+            bgcolor: self.bgcolor,
+            style: self.style,
         }
+    }
 
-        if let Some(ref fgcolor) = self.fgcolor {
-            if has_wrote {
-                res.push(';');
-            }
+    // The rendering itself is done by `fmt_colored`, these are kept as
+    // shortcuts for the tests.
 
-            res.push_str(&fgcolor.to_fg_str());
-        }
+    #[cfg(test)]
+    fn has_colors(&self) -> bool {
+        should_colorize()
+    }
 
-        res.push('m');
+    #[cfg(test)]
+    fn compute_style(&self) -> String {
+        let mut res = String::new();
+        if self.has_colors() {
+            // writing to a `String` can't fail
+            let _ = write_style(&mut res, self.fgcolor, self.bgcolor, self.style);
+        }
         res
     }
 
-    fn escape_inner_reset_sequences(&self) -> Cow<str> {
+    #[cfg(test)]
+    fn escape_inner_reset_sequences(&self) -> Cow<'_, str> {
         if !self.has_colors() || self.is_plain() {
             return self.input.as_str().into();
         }
 
-        // TODO: BoyScoutRule
-        let reset = "\x1B[0m";
-        let style = self.compute_style();
-        let matches: Vec<usize> = self
-            .input
-            .match_indices(reset)
-            .map(|(idx, _)| idx)
-            .collect();
-        if matches.is_empty() {
-            return self.input.as_str().into();
+        escape_inner_reset_sequences(&self.input, &self.compute_style())
+    }
+}
+
+/// Make the reset sequences of nested colored strings go back to `style`
+/// instead of the default style.
+fn escape_inner_reset_sequences<'a>(input: &'a str, style: &str) -> Cow<'a, str> {
+    // TODO: BoyScoutRule
+    let reset = "\x1B[0m";
+    let matches: Vec<usize> = input
+        .match_indices(reset)
+        .map(|(idx, _)| idx)
+        .collect();
+    if matches.is_empty() {
+        return input.into();
+    }
+
+    let mut input = input.to_owned();
+    input.reserve(matches.len() * style.len());
+
+    for (idx_in_matches, offset) in matches.into_iter().enumerate() {
+        // shift the offset to the end of the reset sequence and take in account
+        // the number of matches we have escaped (which shift the index to insert)
+        let offset = offset + reset.len() + idx_in_matches * style.len();
+
+        input.insert_str(offset, style);
+    }
+
+    input.into()
+}
+
+/// Write `input` with the given colors and style, unless `colorize` is false,
+/// through the formatter set by [`formatters::set_formatter`] if any.
+///
+/// The width and precision of the formatter apply to the visible width of the
+/// input, which doesn't count escape sequences and counts wide characters twice.
+fn fmt_colored(
+    f: &mut fmt::Formatter,
+    colorize: bool,
+    input: &str,
+    fgcolor: Option<Color>,
+    bgcolor: Option<Color>,
+    style: Style,
+) -> fmt::Result {
+    let (fgcolor, bgcolor, style) = if colorize {
+        (fgcolor, bgcolor, style)
+    } else {
+        (None, None, NO_STYLE)
+    };
+    #[cfg(feature = "std")]
+    {
+        if let Some(formatter) = formatters::global() {
+            return fmt_with(f, &*formatter, input, fgcolor, bgcolor, style);
         }
+    }
+
+    let mut style_str = String::new();
+    write_style(&mut style_str, fgcolor, bgcolor, style)?;
 
-        let mut input = self.input.clone();
-        input.reserve(matches.len() * style.len());
+    // XXX: see tests. Useful when nesting colored strings
+    let escaped_input = if style_str.is_empty() {
+        input.into()
+    } else {
+        escape_inner_reset_sequences(input, &style_str)
+    };
+
+    let content = match f.precision() {
+        Some(precision) => ansi::truncate_visible(&escaped_input, precision, ""),
+        None => escaped_input,
+    };
+
+    f.write_str(&style_str)?;
+    pad_visible(f, &content)?;
+    if !style_str.is_empty() {
+        f.write_str("\x1B[0m")?;
+    }
+    Ok(())
+}
 
-        for (idx_in_matches, offset) in matches.into_iter().enumerate() {
-            // shift the offset to the end of the reset sequence and take in account
-            // the number of matches we have escaped (which shift the index to insert)
-            let mut offset = offset + reset.len() + idx_in_matches * style.len();
+/// Write `input` with the given colors and style through `formatter`.
+///
+/// The width and precision of the formatter apply to the visible width of the
+/// input, which doesn't count escape sequences and counts wide characters twice.
+fn fmt_with(
+    f: &mut fmt::Formatter,
+    formatter: &dyn formatters::ColoringFormatter,
+    input: &str,
+    fgcolor: Option<Color>,
+    bgcolor: Option<Color>,
+    style: Style,
+) -> fmt::Result {
+    let content = match f.precision() {
+        Some(precision) => ansi::truncate_visible(input, precision, ""),
+        None => input.into(),
+    };
+    fmt_padded(f, formatter, &content, fgcolor, bgcolor, style)
+}
+
+/// Write `content` with the given colors and style through `formatter`, padded
+/// to the width of the formatter. The precision doesn't apply.
+fn fmt_padded(
+    f: &mut fmt::Formatter,
+    formatter: &dyn formatters::ColoringFormatter,
+    content: &str,
+    fgcolor: Option<Color>,
+    bgcolor: Option<Color>,
+    style: Style,
+) -> fmt::Result {
+    if f.width().is_none() {
+        return formatter.format(f, content, fgcolor, bgcolor, style);
+    }
+
+    // the padding takes the colors too
+    let (before, after) = padding(f, ansi::visible_width(content));
+    let fill = f.fill();
+    let mut padded = String::with_capacity(content.len() + before + after);
+    padded.extend(core::iter::repeat_n(fill, before));
+    padded.push_str(content);
+    padded.extend(core::iter::repeat_n(fill, after));
+    formatter.format(f, &padded, fgcolor, bgcolor, style)
+}
+
+/// Write `s` padded to the width of the formatter, with its fill and alignment.
+fn pad_visible(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    let (before, after) = padding(f, ansi::visible_width(s));
+    let fill = f.fill();
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+    f.write_str(s)?;
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+    Ok(())
+}
 
-            for cchar in style.chars() {
-                input.insert(offset, cchar);
-                offset += 1;
+/// The number of fill characters to write before and after something
+/// `visible_width` columns wide, to pad it to the width of the formatter.
+fn padding(f: &fmt::Formatter, visible_width: usize) -> (usize, usize) {
+    let padding = f
+        .width()
+        .map_or(0, |width| width.saturating_sub(visible_width));
+    match f.align() {
+        Some(fmt::Alignment::Right) => (padding, 0),
+        Some(fmt::Alignment::Center) => (padding / 2, padding - padding / 2),
+        _ => (0, padding),
+    }
+}
+
+fn should_colorize() -> bool {
+    should_colorize_for(control::Stream::Stdout)
+}
+
+#[cfg(not(feature = "no-color"))]
+fn should_colorize_for(stream: control::Stream) -> bool {
+    control::SHOULD_COLORIZE.for_stream(stream)
+}
+
+#[cfg(feature = "no-color")]
+fn should_colorize_for(_stream: control::Stream) -> bool {
+    false
+}
+
+#[cfg(all(feature = "std", not(feature = "no-color")))]
+fn should_colorize_for_writer<W: std::io::IsTerminal>(writer: &W) -> bool {
+    control::SHOULD_COLORIZE.for_writer(writer)
+}
+
+#[cfg(all(feature = "std", feature = "no-color"))]
+fn should_colorize_for_writer<W: std::io::IsTerminal>(_writer: &W) -> bool {
+    false
+}
+
+/// Used by the code generated by the markup macros.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub fn should_colorize_for(stream: ::control::Stream) -> bool {
+        ::should_colorize_for(stream)
+    }
+}
+
+/// Write the escape sequence setting the given colors and style, or nothing
+/// if there are none. Doesn't allocate.
+fn write_style<W: fmt::Write>(
+    w: &mut W,
+    fgcolor: Option<Color>,
+    bgcolor: Option<Color>,
+    style: Style,
+) -> fmt::Result {
+    if fgcolor.is_none() && bgcolor.is_none() && style == NO_STYLE {
+        return Ok(());
+    }
+
+    w.write_str("\x1B[")?;
+    write_style_params(&mut SgrParams::new(w), fgcolor, bgcolor, style)?;
+    w.write_char('m')
+}
+
+fn write_style_params<W: fmt::Write>(
+    params: &mut SgrParams<W>,
+    fgcolor: Option<Color>,
+    bgcolor: Option<Color>,
+    style: Style,
+) -> fmt::Result {
+    for s in style.iter() {
+        params.push(s.to_str())?;
+    }
+    if let Some(bgcolor) = bgcolor {
+        params.push_color(bgcolor, false)?;
+    }
+    if let Some(fgcolor) = fgcolor {
+        params.push_color(fgcolor, true)?;
+    }
+    Ok(())
+}
+
+/// The colors and style applied to a piece of text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Attributes {
+    fgcolor: Option<Color>,
+    bgcolor: Option<Color>,
+    style: Style,
+}
+
+impl Attributes {
+    const PLAIN: Attributes = Attributes {
+        fgcolor: None,
+        bgcolor: None,
+        style: NO_STYLE,
+    };
+
+    fn is_plain(&self) -> bool {
+        *self == Attributes::PLAIN
+    }
+}
+
+/// Write the shortest escape sequence going from the `from` attributes to
+/// the `to` ones: either switching off and on only what differs, or a reset
+/// followed by the whole of `to`.
+fn write_transition<W: fmt::Write>(w: &mut W, from: Attributes, to: Attributes) -> fmt::Result {
+    if from == to {
+        return Ok(());
+    }
+    if to.is_plain() {
+        return w.write_str("\x1B[0m");
+    }
+    if from.is_plain() {
+        return write_style(w, to.fgcolor, to.bgcolor, to.style);
+    }
+
+    let mut diff = String::new();
+    write_diff_params(&mut SgrParams::new(&mut diff), from, to)?;
+
+    let mut reset = String::new();
+    {
+        let mut params = SgrParams::new(&mut reset);
+        params.push("0")?;
+        write_style_params(&mut params, to.fgcolor, to.bgcolor, to.style)?;
+    }
+
+    let params = if reset.len() < diff.len() { reset } else { diff };
+    write!(w, "\x1B[{}m", params)
+}
+
+fn write_diff_params<W: fmt::Write>(
+    params: &mut SgrParams<W>,
+    from: Attributes,
+    to: Attributes,
+) -> fmt::Result {
+    // bold and dimmed are switched off together, the one staying on has to
+    // be switched on again
+    let mut intensity_off = false;
+    for s in (from.style - to.style).iter() {
+        match s {
+            Styles::Bold | Styles::Dimmed if intensity_off => (),
+            Styles::Bold | Styles::Dimmed => {
+                params.push(s.to_off_str())?;
+                intensity_off = true;
             }
+            _ => params.push(s.to_off_str())?,
+        }
+    }
+    for s in to.style.iter() {
+        let intensity = s == Styles::Bold || s == Styles::Dimmed;
+        if !from.style.contains(s) || (intensity && intensity_off) {
+            params.push(s.to_str())?;
         }
+    }
 
-        input.into()
+    if from.bgcolor != to.bgcolor {
+        match to.bgcolor {
+            Some(bgcolor) => params.push_color(bgcolor, false)?,
+            None => params.push("49")?,
+        }
+    }
+    if from.fgcolor != to.fgcolor {
+        match to.fgcolor {
+            Some(fgcolor) => params.push_color(fgcolor, true)?,
+            None => params.push("39")?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes the `;` separated parameters of an SGR sequence.
+struct SgrParams<'w, W: 'w> {
+    w: &'w mut W,
+    empty: bool,
+}
+
+impl<'w, W: fmt::Write> SgrParams<'w, W> {
+    fn new(w: &'w mut W) -> Self {
+        SgrParams { w, empty: true }
+    }
+
+    fn separate(&mut self) -> fmt::Result {
+        if !self.empty {
+            self.w.write_char(';')?;
+        }
+        self.empty = false;
+        Ok(())
+    }
+
+    fn push(&mut self, param: &str) -> fmt::Result {
+        self.separate()?;
+        self.w.write_str(param)
+    }
+
+    fn push_color(&mut self, color: Color, foreground: bool) -> fmt::Result {
+        self.separate()?;
+        match (color, foreground) {
+            (Color::TrueColor { r, g, b }, true) => write!(self.w, "38;2;{};{};{}", r, g, b),
+            (Color::TrueColor { r, g, b }, false) => write!(self.w, "48;2;{};{};{}", r, g, b),
+            (_, true) => self.w.write_str(&color.to_fg_str()),
+            (_, false) => self.w.write_str(&color.to_bg_str()),
+        }
     }
 }
 
@@ -331,6 +777,12 @@ impl Deref for ColoredString {
     }
 }
 
+impl<'a> From<ColoredStr<'a>> for ColoredString {
+    fn from(s: ColoredStr<'a>) -> Self {
+        s.into_owned()
+    }
+}
+
 impl<'a> From<&'a str> for ColoredString {
     fn from(s: &'a str) -> Self {
         ColoredString {
@@ -366,7 +818,7 @@ impl Colorize for ColoredString {
     }
 }
 
-impl<'a> Colorize for &'a str {
+impl Colorize for &str {
     fn color<S: Into<Color>>(self, color: S) -> ColoredString {
         ColoredString {
             fgcolor: Some(color.into()),
@@ -402,17 +854,54 @@ impl<'a> Colorize for &'a str {
 
 impl fmt::Display for ColoredString {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if !self.has_colors() || self.is_plain() {
-            return <String as fmt::Display>::fmt(&self.input, f);
-        }
+        fmt_colored(
+            f,
+            should_colorize(),
+            &self.input,
+            self.fgcolor,
+            self.bgcolor,
+            self.style,
+        )
+    }
+}
 
-        // XXX: see tests. Useful when nesting colored strings
-        let escaped_input = self.escape_inner_reset_sequences();
+/// A [`ColoredString`] displayed for a given [`Stream`](control::Stream).
+///
+/// Obtained with [`ColoredString::for_stream`].
+#[derive(Clone, Copy, Debug)]
+pub struct ForStream<'a> {
+    string: &'a ColoredString,
+    stream: control::Stream,
+}
 
-        f.write_str(&self.compute_style())?;
-        escaped_input.fmt(f)?;
-        f.write_str("\x1B[0m")?;
-        Ok(())
+impl<'a> fmt::Display for ForStream<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let string = self.string;
+        fmt_colored(
+            f,
+            should_colorize_for(self.stream),
+            &string.input,
+            string.fgcolor,
+            string.bgcolor,
+            string.style,
+        )
+    }
+}
+
+impl<'a> fmt::Display for formatters::FormatWith<'a, ColoredString> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let string = self.value;
+        if cfg!(feature = "no-color") {
+            return fmt_with(f, self.formatter, &string.input, None, None, NO_STYLE);
+        }
+        fmt_with(
+            f,
+            self.formatter,
+            &string.input,
+            string.fgcolor,
+            string.bgcolor,
+            string.style,
+        )
     }
 }
 
@@ -432,6 +921,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn it_works() {
         let toto = "toto";
         println!("{}", toto.red());
@@ -618,6 +1108,135 @@ mod tests {
         assert_eq!(expected, output);
     }
 
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn padding_ignores_escape_sequences() {
+        control::with_colors(true, || {
+            let nested = format!("a{}b", "x".red()).blue();
+            assert_eq!(
+                format!("\x1B[34ma\x1B[31mx\x1B[0m\x1B[34mb   \x1B[0m"),
+                format!("{:<6}", nested)
+            );
+
+            let plain = format!("a{}b", "x".red()).normal();
+            assert_eq!(format!("  a{}b", "x".red()), format!("{:>5}", plain));
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn padding_alignment_and_fill() {
+        control::with_colors(true, || {
+            assert_eq!("\x1B[32mabc   \x1B[0m", format!("{:6}", "abc".green()));
+            assert_eq!("\x1B[32m   abc\x1B[0m", format!("{:>6}", "abc".green()));
+            assert_eq!("\x1B[32m**abc**\x1B[0m", format!("{:*^7}", "abc".green()));
+            assert_eq!("\x1B[32m-abc--\x1B[0m", format!("{:-^6}", "abc".green()));
+            assert_eq!("\x1B[32mabcdef\x1B[0m", format!("{:>3}", "abcdef".green()));
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn padding_uses_display_width() {
+        control::with_colors(true, || {
+            assert_eq!("\x1B[31m  日本\x1B[0m", format!("{:>6}", "日本".red()));
+            assert_eq!("\x1B[31m🦀 |\x1B[0m", format!("{:|<4}", "🦀 ".red()));
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn precision_keeps_nested_sequences_whole() {
+        control::with_colors(true, || {
+            let nested = format!("ab{}", "cd".red()).blue();
+            assert_eq!(
+                "\x1B[34mab\x1B[31mc\x1B[0m\x1B[0m",
+                format!("{:.3}", nested)
+            );
+            assert_eq!("\x1B[34m日 \x1B[0m", format!("{:3.3}", "日本".blue()));
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn format_with() {
+        use formatters::{Ansi, Html, NoColor};
+
+        let cstr = "a<b".red().on_black().underline();
+        assert_eq!(
+            r#"<span style="color:#cd0000;background-color:#000000;text-decoration:underline">a&lt;b</span>"#,
+            cstr.format_with(&Html).to_string()
+        );
+        assert_eq!("  a<", format!("{:>4.2}", cstr.format_with(&NoColor)));
+        assert_eq!(
+            "\x1B[4;40;31m a<b\x1B[0m",
+            format!("{:>4}", cstr.format_with(&Ansi))
+        );
+    }
+
+    #[test]
+    fn truncate_visible() {
+        let cstr = "hello world".green().bold();
+        assert_eq!("hello…".green().bold(), cstr.truncate_visible(6, "…"));
+        assert_eq!(cstr, cstr.truncate_visible(11, "…"));
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn wrap() {
+        control::with_colors(true, || {
+            assert_eq!(
+                vec!["\x1B[1;32mhello\x1B[0m", "\x1B[1;32mworld\x1B[0m"],
+                "hello world".green().bold().wrap(6)
+            );
+            assert_eq!(vec!["hello", "world"], "hello world".normal().wrap(6));
+        });
+    }
+
+    fn transition(from: ColoredString, to: ColoredString) -> String {
+        let mut out = String::new();
+        write_transition(&mut out, from.attributes(), to.attributes()).unwrap();
+        out
+    }
+
+    #[test]
+    fn transitions() {
+        assert_eq!("", transition("".red(), "".red()));
+        assert_eq!("\x1B[0m", transition("".red().bold(), "".normal()));
+        assert_eq!("\x1B[1;31m", transition("".normal(), "".red().bold()));
+        assert_eq!("\x1B[22m", transition("".red().bold(), "".red()));
+        assert_eq!(
+            "\x1B[22;2m",
+            transition("".red().bold().dimmed(), "".red().dimmed())
+        );
+        assert_eq!(
+            "\x1B[39m",
+            transition("".red().italic().underline(), "".italic().underline())
+        );
+        assert_eq!(
+            "\x1B[48;2;1;2;3m",
+            transition("".on_red(), "".on_truecolor(1, 2, 3))
+        );
+    }
+
+    #[test]
+    fn transitions_prefer_reset_when_shorter() {
+        assert_eq!("\x1B[0;32m", transition("".red().bold(), "".green()));
+        assert_eq!("\x1B[0;34m", transition("".red().on_blue(), "".blue()));
+    }
+
+    #[test]
+    fn for_stream() {
+        let cstr = "plain".normal();
+        assert_eq!("plain  ", format!("{:7}", cstr.for_stream(control::Stream::Stderr)));
+    }
+
+    #[test]
+    fn padding_plain_string() {
+        assert_eq!("  日本", format!("{:>6}", "日本".normal()));
+        assert_eq!("ab..", format!("{:.<4.2}", "abc".normal()));
+    }
+
     #[test]
     fn color_fn() {
         assert_eq!("blue".blue(), "blue".color("blue"))
@@ -655,8 +1274,44 @@ mod tests {
         let cstring = cstring.bold().italic();
         assert_eq!(cstring.fgcolor(), Some(Color::Blue));
         assert_eq!(cstring.bgcolor(), Some(Color::BrightYellow));
-        assert_eq!(cstring.style().contains(Styles::Bold), true);
-        assert_eq!(cstring.style().contains(Styles::Italic), true);
-        assert_eq!(cstring.style().contains(Styles::Dimmed), false);
+        assert!(cstring.style().contains(Styles::Bold));
+        assert!(cstring.style().contains(Styles::Italic));
+        assert!(!cstring.style().contains(Styles::Dimmed));
+    }
+
+    #[cfg(feature = "serde")]
+    mod serialize {
+        extern crate serde_json;
+
+        use super::*;
+
+        #[test]
+        fn serialize() {
+            let cstring = "hello".red().on_bright_black().bold();
+            assert_eq!(
+                r#"{"input":"hello","fgcolor":"red","bgcolor":"bright black","style":["bold"]}"#,
+                serde_json::to_string(&cstring).unwrap()
+            );
+        }
+
+        #[test]
+        fn round_trip() {
+            let cstrings = [
+                "plain".normal(),
+                "true".truecolor(1, 2, 3).on_truecolor(4, 5, 6),
+                "styled".italic().underline().magenta(),
+                format!("nested {} string", "colored".green()).blue(),
+            ];
+            for cstring in cstrings.iter() {
+                let json = serde_json::to_string(cstring).unwrap();
+                assert_eq!(*cstring, serde_json::from_str::<ColoredString>(&json).unwrap());
+            }
+        }
+
+        #[test]
+        fn missing_attributes() {
+            let cstring: ColoredString = serde_json::from_str(r#"{"input":"hi"}"#).unwrap();
+            assert_eq!("hi".normal(), cstring);
+        }
     }
 }
@@ -0,0 +1,310 @@
+//! Colored templates written with tags, parsed at runtime.
+//!
+//! Use it for templates that come from the user, such as a prompt or a status
+//! line in a configuration file. For templates known at compile time, the
+//! `cformat!` family of macros (behind the `macros` feature) takes the same
+//! tags.
+//!
+//! A tag is a [`StyleSpec`] where underscores can stand for spaces:
+//! `<red>`, `<bright_blue>`, `<on_red>`, `<#ff8000>`, `<bold>` or even
+//! `<bold red on black>`. It applies until the matching closing tag, `</red>`,
+//! on top of the tags around it. Write `<<` for a literal `<`.
+//!
+//! ```rust
+//! # use colored::*;
+//! let line = colored::markup::parse("<green>main</green> <bold><<2></bold>").unwrap();
+//! assert_eq!(line, "main".green() + " " + "<2>".bold());
+//!
+//! let err = colored::markup::parse("<green>main</gren>").unwrap_err();
+//! assert_eq!(err.offset(), 11);
+//! ```
+
+use std::{error::Error, fmt};
+
+use theme::{ParseStyleSpecError, StyleSpec};
+use {ColoredString, ColoredStrings};
+
+/// The error returned when a template can't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseMarkupError {
+    offset: usize,
+    kind: ErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ErrorKind {
+    UnclosedBracket,
+    EmptyTag,
+    UnknownTag(String, ParseStyleSpecError),
+    UnclosedTag(String),
+    UnexpectedClosingTag(String),
+    MismatchedClosingTag(String, String),
+}
+
+impl ParseMarkupError {
+    /// The position of the error in the template, in bytes.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for ParseMarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ErrorKind::UnclosedBracket => {
+                f.write_str("`<` is never closed, write `<<` for a `<`")?
+            }
+            ErrorKind::EmptyTag => f.write_str("empty tag, write `<<` for a `<`")?,
+            ErrorKind::UnknownTag(ref tag, ref err) => {
+                write!(f, "invalid tag `<{}>`: {}", tag, err)?
+            }
+            ErrorKind::UnclosedTag(ref tag) => write!(f, "`<{}>` is never closed", tag)?,
+            ErrorKind::UnexpectedClosingTag(ref tag) => write!(f, "`</{}>` closes no tag", tag)?,
+            ErrorKind::MismatchedClosingTag(ref tag, ref open) => {
+                write!(f, "`</{}>` doesn't close `<{}>`", tag, open)?
+            }
+        }
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+impl Error for ParseMarkupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            ErrorKind::UnknownTag(_, ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a template into colored spans.
+///
+/// ```rust
+/// # use colored::*;
+/// let prompt = colored::markup::parse("<bold><blue>~/src</blue> <on_bright_black>$</on_bright_black></bold> ")?;
+/// println!("{}", prompt);
+/// # Ok::<(), colored::markup::ParseMarkupError>(())
+/// ```
+pub fn parse(template: &str) -> Result<ColoredStrings, ParseMarkupError> {
+    let mut spans = ColoredStrings::new();
+    // the open tags, with their offset and the colors and style inside them
+    let mut open: Vec<(&str, usize, ColoredString)> = Vec::new();
+    let mut text = String::new();
+
+    let mut rest = template;
+    while let Some(idx) = rest.find('<') {
+        let offset = template.len() - rest.len() + idx;
+        text.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+
+        if rest.starts_with("<<") {
+            text.push('<');
+            rest = &rest[2..];
+            continue;
+        }
+
+        let end = rest.find('>').ok_or(ParseMarkupError {
+            offset,
+            kind: ErrorKind::UnclosedBracket,
+        })?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        // a spec without words is valid, but not as a tag
+        let name = tag.strip_prefix('/').unwrap_or(tag);
+        if name
+            .split(|c: char| c == '_' || c.is_whitespace())
+            .all(str::is_empty)
+        {
+            return Err(ParseMarkupError {
+                offset,
+                kind: ErrorKind::EmptyTag,
+            });
+        }
+
+        let current = open
+            .last()
+            .map_or_else(ColoredString::default, |open| open.2.clone());
+        if !text.is_empty() {
+            spans.push(ColoredString {
+                input: std::mem::take(&mut text),
+                ..current.clone()
+            });
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            match open.pop() {
+                Some((open_tag, _, _)) if open_tag == name => (),
+                Some((open_tag, _, _)) => {
+                    return Err(ParseMarkupError {
+                        offset,
+                        kind: ErrorKind::MismatchedClosingTag(name.into(), open_tag.into()),
+                    })
+                }
+                None => {
+                    return Err(ParseMarkupError {
+                        offset,
+                        kind: ErrorKind::UnexpectedClosingTag(name.into()),
+                    })
+                }
+            }
+        } else {
+            let spec: StyleSpec =
+                tag.replace('_', " ")
+                    .parse()
+                    .map_err(|err| ParseMarkupError {
+                        offset,
+                        kind: ErrorKind::UnknownTag(tag.into(), err),
+                    })?;
+            open.push((tag, offset, spec.apply(current)));
+        }
+    }
+
+    if let Some((tag, offset, _)) = open.pop() {
+        return Err(ParseMarkupError {
+            offset,
+            kind: ErrorKind::UnclosedTag(tag.into()),
+        });
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        spans.push(text.as_str());
+    }
+    Ok(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Color, Colorize, Styles};
+
+    #[test]
+    fn plain() {
+        assert_eq!(
+            ColoredStrings::from("no tags".normal()),
+            parse("no tags").unwrap()
+        );
+        assert_eq!(ColoredStrings::new(), parse("").unwrap());
+    }
+
+    #[test]
+    fn nested_tags() {
+        assert_eq!(
+            "a".red() + "b".red().bold() + "c".red() + "d",
+            parse("<red>a<bold>b</bold>c</red>d").unwrap()
+        );
+        assert_eq!(
+            ColoredStrings::from("x".green().on_blue()),
+            parse("<red><on_blue><green>x</green></on_blue></red>").unwrap()
+        );
+    }
+
+    #[test]
+    fn every_color_and_style() {
+        let colors = [
+            Color::Black,
+            Color::Red,
+            Color::Green,
+            Color::Yellow,
+            Color::Blue,
+            Color::Magenta,
+            Color::Cyan,
+            Color::White,
+            Color::BrightBlack,
+            Color::BrightRed,
+            Color::BrightGreen,
+            Color::BrightYellow,
+            Color::BrightBlue,
+            Color::BrightMagenta,
+            Color::BrightCyan,
+            Color::BrightWhite,
+            Color::TrueColor {
+                r: 10,
+                g: 128,
+                b: 255,
+            },
+        ];
+        for &color in &colors {
+            let tag = color.to_name().replace(' ', "_");
+            let parsed = parse(&format!("<{0}>x</{0}>", tag)).unwrap();
+            assert_eq!(ColoredStrings::from("x".color(color)), parsed);
+
+            let parsed = parse(&format!("<on_{0}>x</on_{0}>", tag)).unwrap();
+            assert_eq!(ColoredStrings::from("x".on_color(color)), parsed);
+        }
+
+        let styles = [
+            Styles::Bold,
+            Styles::Dimmed,
+            Styles::Italic,
+            Styles::Underline,
+            Styles::Blink,
+            Styles::Reversed,
+            Styles::Hidden,
+            Styles::Strikethrough,
+        ];
+        for &style in &styles {
+            let parsed = parse(&format!("<{0}>x</{0}>", style.name())).unwrap();
+            assert_eq!(ColoredStrings::from("x".normal().add_style(style)), parsed);
+        }
+    }
+
+    #[test]
+    fn spec_tags() {
+        assert_eq!(
+            ColoredStrings::from("x".magenta().on_bright_black().underline()),
+            parse("<underline purple on bright black>x</underline purple on bright black>")
+                .unwrap()
+        );
+        assert_eq!(
+            ColoredStrings::from("x".reversed()),
+            parse("<reverse>x</reverse>").unwrap()
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(
+            "a < b ".normal() + "<<".red() + " >",
+            parse("a << b <red><<<<</red> >").unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse("ab <rouge>c</rouge>").unwrap_err();
+        assert_eq!(3, err.offset());
+        assert_eq!(
+            "invalid tag `<rouge>`: unknown color or style `rouge` at offset 3",
+            err.to_string()
+        );
+        assert!(err.source().is_some());
+
+        let err = parse("a <red").unwrap_err();
+        assert_eq!(
+            "`<` is never closed, write `<<` for a `<` at offset 2",
+            err.to_string()
+        );
+
+        let err = parse("<red><bold>a</red>").unwrap_err();
+        assert_eq!(
+            "`</red>` doesn't close `<bold>` at offset 12",
+            err.to_string()
+        );
+
+        let err = parse("a</red>").unwrap_err();
+        assert_eq!("`</red>` closes no tag at offset 1", err.to_string());
+
+        let err = parse("a<></>").unwrap_err();
+        assert_eq!(1, err.offset());
+        assert_eq!(
+            "empty tag, write `<<` for a `<` at offset 1",
+            err.to_string()
+        );
+        assert_eq!(6, parse("<bold></>").unwrap_err().offset());
+        assert_eq!(0, parse("< _ >a</ _ >").unwrap_err().offset());
+
+        let err = parse("x<red>a").unwrap_err();
+        assert_eq!("`<red>` is never closed at offset 1", err.to_string());
+    }
+}
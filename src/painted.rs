@@ -0,0 +1,398 @@
+use core::fmt;
+
+use color::Color;
+#[cfg(feature = "std")]
+use formatters::ColoringFormatter;
+use paste::paste;
+use style::{Style, Styles, NO_STYLE};
+
+/// Generate the named color and style setters of a type that already has
+/// `color`, `on_color`, `add_style` and `remove_style` methods returning `Self`.
+macro_rules! painting_methods {
+    ($($color:ident),+ ; $($style:ident),+ $(,)?) => {
+        paste! {
+            $(
+                #[doc = concat!("Set the foreground color to `Color::", stringify!($color), "`.")]
+                pub fn [< $color:snake:lower >](self) -> Self {
+                    self.color(Color::$color)
+                }
+
+                #[doc = concat!("Set the background color to `Color::", stringify!($color), "`.")]
+                pub fn [< on_ $color:snake:lower >](self) -> Self {
+                    self.on_color(Color::$color)
+                }
+
+                #[doc = concat!("Set the foreground color to `Color::Bright", stringify!($color), "`.")]
+                pub fn [< bright_ $color:snake:lower >](self) -> Self {
+                    self.color(Color::[< Bright $color >])
+                }
+
+                #[doc = concat!("Set the background color to `Color::Bright", stringify!($color), "`.")]
+                pub fn [< on_bright_ $color:snake:lower >](self) -> Self {
+                    self.on_color(Color::[< Bright $color >])
+                }
+            )+
+
+            $(
+                #[doc = concat!("Add the `Styles::", stringify!($style), "` style.")]
+                pub fn [< $style:snake:lower >](self) -> Self {
+                    self.add_style(Styles::$style)
+                }
+
+                #[doc = concat!("Remove the `Styles::", stringify!($style), "` style.")]
+                pub fn [< remove_ $style:snake:lower >](self) -> Self {
+                    self.remove_style(Styles::$style)
+                }
+            )+
+
+            /// Set the foreground color to an RGB value.
+            pub fn truecolor(self, r: u8, g: u8, b: u8) -> Self {
+                self.color(Color::TrueColor { r, g, b })
+            }
+
+            /// Set the background color to an RGB value.
+            pub fn on_truecolor(self, r: u8, g: u8, b: u8) -> Self {
+                self.on_color(Color::TrueColor { r, g, b })
+            }
+
+            /// Remove all colors and styles.
+            pub fn normal(self) -> Self {
+                self.clear()
+            }
+        }
+    };
+}
+
+/// Give color to any value, without turning it into a `String` first.
+///
+/// The result is formatted with the same formatting traits as the value:
+/// `Display`, `Debug`, `LowerHex`, etc.
+///
+/// ```rust
+/// # use colored::*;
+/// let answer = 42;
+/// println!("the answer is {}", answer.painted().bright_green().bold());
+/// println!("{}", std::path::Path::new("/etc/hosts").display().painted().underline());
+/// println!("{:#?}", vec![1, 2, 3].painted().red());
+/// println!("{:#010x}", answer.painted().blue());
+/// ```
+pub trait Paint {
+    /// Borrow the value to give it colors and styles.
+    fn painted(&self) -> Painted<'_, Self> {
+        Painted {
+            value: self,
+            fgcolor: None,
+            bgcolor: None,
+            style: NO_STYLE,
+        }
+    }
+}
+
+impl<T: ?Sized> Paint for T {}
+
+/// A borrowed value that may have color and/or style applied to it.
+///
+/// The colors are only written when the value is formatted, honoring
+/// [`SHOULD_COLORIZE`](crate::control::SHOULD_COLORIZE) just like [`ColoredString`](crate::ColoredString).
+/// All the formatting traits of the value are forwarded, and formatting flags
+/// such as the width or `#` apply to the value itself.
+///
+/// Obtained with [`Paint::painted`].
+pub struct Painted<'a, T: ?Sized + 'a> {
+    value: &'a T,
+    fgcolor: Option<Color>,
+    bgcolor: Option<Color>,
+    style: Style,
+}
+
+impl<'a, T: ?Sized> Clone for Painted<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: ?Sized> Copy for Painted<'a, T> {}
+
+impl<'a, T: ?Sized> Painted<'a, T> {
+    /// Set the foreground color.
+    pub fn color<S: Into<Color>>(mut self, color: S) -> Self {
+        self.fgcolor = Some(color.into());
+        self
+    }
+
+    /// Set the background color.
+    pub fn on_color<S: Into<Color>>(mut self, color: S) -> Self {
+        self.bgcolor = Some(color.into());
+        self
+    }
+
+    /// Add styles on top of the current ones.
+    pub fn add_style<S: Into<Style>>(mut self, style: S) -> Self {
+        self.style += style.into();
+        self
+    }
+
+    /// Remove styles from the current ones.
+    pub fn remove_style<S: Into<Style>>(mut self, style: S) -> Self {
+        self.style -= style.into();
+        self
+    }
+
+    /// Remove all colors and styles.
+    pub fn clear(self) -> Self {
+        Painted {
+            value: self.value,
+            fgcolor: None,
+            bgcolor: None,
+            style: NO_STYLE,
+        }
+    }
+
+    painting_methods!(
+        Black, Red, Green, Yellow, Blue, Magenta, Cyan, White;
+        Bold, Dimmed, Italic, Underline, Blink, Reversed, Hidden, Strikethrough,
+    );
+
+    /// Get the painted value.
+    pub fn value(&self) -> &'a T {
+        self.value
+    }
+
+    /// Get the current foreground color applied.
+    pub fn fgcolor(&self) -> Option<Color> {
+        self.fgcolor
+    }
+
+    /// Get the current background color applied.
+    pub fn bgcolor(&self) -> Option<Color> {
+        self.bgcolor
+    }
+
+    /// Get the current [`Style`].
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    /// Checks if the value has no color or styling.
+    pub fn is_plain(&self) -> bool {
+        self.bgcolor.is_none() && self.fgcolor.is_none() && self.style == NO_STYLE
+    }
+}
+
+impl<'a, T: ?Sized> Painted<'a, T> {
+    fn fmt_with<F>(&self, f: &mut fmt::Formatter, fmt_value: F) -> fmt::Result
+    where
+        F: Fn(&T, &mut fmt::Formatter) -> fmt::Result,
+    {
+        #[cfg(feature = "std")]
+        {
+            if let Some(formatter) = ::formatters::global() {
+                return self.fmt_through(f, &*formatter, &fmt_value);
+            }
+        }
+        if !::should_colorize() || self.is_plain() {
+            return fmt_value(self.value, f);
+        }
+
+        ::write_style(f, self.fgcolor, self.bgcolor, self.style)?;
+        fmt_value(self.value, f)?;
+        f.write_str("\x1B[0m")
+    }
+
+    /// Write the value through the formatter set by
+    /// [`formatters::set_formatter`](::formatters::set_formatter). The fill,
+    /// alignment and width of `f` pad it inside the colors.
+    #[cfg(feature = "std")]
+    fn fmt_through<F>(
+        &self,
+        f: &mut fmt::Formatter,
+        formatter: &dyn ColoringFormatter,
+        fmt_value: &F,
+    ) -> fmt::Result
+    where
+        F: Fn(&T, &mut fmt::Formatter) -> fmt::Result,
+    {
+        let value = format_value(f, &FmtValue(self.value, fmt_value));
+        if ::should_colorize() {
+            ::fmt_padded(f, formatter, &value, self.fgcolor, self.bgcolor, self.style)
+        } else {
+            ::fmt_padded(f, formatter, &value, None, None, NO_STYLE)
+        }
+    }
+}
+
+/// Formats a value with one of its formatting traits, through `Display`.
+#[cfg(feature = "std")]
+struct FmtValue<'a, T: ?Sized + 'a, F: 'a>(&'a T, &'a F);
+
+#[cfg(feature = "std")]
+impl<'a, T: ?Sized, F> fmt::Display for FmtValue<'a, T, F>
+where
+    F: Fn(&T, &mut fmt::Formatter) -> fmt::Result,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.1)(self.0, f)
+    }
+}
+
+/// Format `value` with the sign, `#`, `0` and precision flags of `f`. The width
+/// only applies with `0`, the fill and alignment are left to the caller.
+#[cfg(feature = "std")]
+fn format_value<V: fmt::Display>(f: &fmt::Formatter, value: &V) -> String {
+    let width = match f.width() {
+        Some(width) if f.sign_aware_zero_pad() => width,
+        _ => 0,
+    };
+    macro_rules! format_value {
+        ($($plus:tt $alternate:tt $zero:tt => $flags:tt),+ $(,)?) => {
+            match (f.sign_plus(), f.alternate(), f.sign_aware_zero_pad(), f.precision()) {
+                $(
+                    ($plus, $alternate, $zero, None) => {
+                        format!(concat!("{:", $flags, "w$}"), value, w = width)
+                    }
+                    ($plus, $alternate, $zero, Some(p)) => {
+                        format!(concat!("{:", $flags, "w$.p$}"), value, w = width, p = p)
+                    }
+                )+
+            }
+        };
+    }
+    format_value!(
+        false false false => "",
+        true false false => "+",
+        false true false => "#",
+        true true false => "+#",
+        false false true => "0",
+        true false true => "+0",
+        false true true => "#0",
+        true true true => "+#0",
+    )
+}
+
+macro_rules! forward_fmt_traits {
+    ($($trait:ident),+ $(,)?) => {
+        $(
+            impl<'a, T: fmt::$trait + ?Sized> fmt::$trait for Painted<'a, T> {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    self.fmt_with(f, fmt::$trait::fmt)
+                }
+            }
+        )+
+    };
+}
+
+forward_fmt_traits!(
+    Display, Debug, LowerHex, UpperHex, Binary, Octal, LowerExp, UpperExp, Pointer,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use control;
+    use Colorize;
+
+    #[test]
+    fn same_attributes_as_colorize() {
+        let painted = 12.painted().red().on_bright_blue().bold().italic();
+        let colored = "12".red().on_bright_blue().bold().italic();
+
+        assert_eq!(colored.fgcolor(), painted.fgcolor());
+        assert_eq!(colored.bgcolor(), painted.bgcolor());
+        assert_eq!(colored.style(), painted.style());
+        assert_eq!(&12, painted.value());
+    }
+
+    #[test]
+    fn clear() {
+        let painted = "x".painted().green().underline().remove_underline();
+        assert!(!painted.is_plain());
+        assert!(painted.clear().is_plain());
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn same_output_as_colored_string() {
+        control::with_colors(true, || {
+            assert_eq!(
+                "3.5".blue().bold().to_string(),
+                3.5.painted().blue().bold().to_string()
+            );
+            assert_eq!(
+                "rgb".truecolor(1, 2, 3).on_truecolor(4, 5, 6).to_string(),
+                "rgb"
+                    .painted()
+                    .truecolor(1, 2, 3)
+                    .on_truecolor(4, 5, 6)
+                    .to_string()
+            );
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn formatting_applies_to_the_value() {
+        control::with_colors(true, || {
+            assert_eq!("\x1B[32m  7\x1B[0m", format!("{:>3}", 7.painted().green()));
+            assert_eq!(
+                "\x1B[32m1.23\x1B[0m",
+                format!("{:.2}", 1.23456.painted().green())
+            );
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn forwards_formatting_traits() {
+        control::with_colors(true, || {
+            let n = 255;
+            assert_eq!("\x1B[31mff\x1B[0m", format!("{:x}", n.painted().red()));
+            assert_eq!("\x1B[31m0xFF\x1B[0m", format!("{:#X}", n.painted().red()));
+            assert_eq!(
+                "\x1B[31m0b11111111\x1B[0m",
+                format!("{:#b}", n.painted().red())
+            );
+            assert_eq!("\x1B[31m0o377\x1B[0m", format!("{:#o}", n.painted().red()));
+            assert_eq!(
+                "\x1B[31m2.55e2\x1B[0m",
+                format!("{:e}", 255.0.painted().red())
+            );
+            assert_eq!(
+                "\x1B[31m2.55E2\x1B[0m",
+                format!("{:E}", 255.0.painted().red())
+            );
+            assert_eq!(
+                "\x1B[31m000000ff\x1B[0m",
+                format!("{:08x}", n.painted().red())
+            );
+
+            let r = &n;
+            assert_eq!(
+                format!("\x1B[31m{:p}\x1B[0m", r),
+                format!("{:p}", Paint::painted(&r).red())
+            );
+        });
+    }
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn forwards_debug() {
+        control::with_colors(true, || {
+            let v = vec!["a", "b"];
+            assert_eq!(
+                "\x1B[1m[\"a\", \"b\"]\x1B[0m",
+                format!("{:?}", v.painted().bold())
+            );
+            assert_eq!(
+                format!("\x1B[1m{:#?}\x1B[0m", v),
+                format!("{:#?}", v.painted().bold())
+            );
+            assert_eq!("\x1B[1m   42\x1B[0m", format!("{:5?}", 42.painted().bold()));
+        });
+    }
+
+    #[test]
+    fn plain_is_value() {
+        assert_eq!("  7", format!("{:>3}", 7.painted()));
+        assert_eq!("[1, 2]", format!("{:?}", [1, 2].painted()));
+    }
+}
@@ -1,4 +1,9 @@
-use std::ops;
+#[cfg(test)]
+use alloc::vec::Vec;
+use core::{ops, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{de, ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
 
 const CLEARV: u8 = 0b0000_0000;
 const BOLD: u8 = 0b0000_0001;
@@ -45,7 +50,7 @@ impl ops::Add<Styles> for Styles {
 }
 
 impl Styles {
-    fn to_str<'a>(self) -> &'a str {
+    pub(crate) fn to_str<'a>(self) -> &'a str {
         match self {
             Styles::Clear => "", // unreachable, but we don't want to panic
             Styles::Bold => "1",
@@ -59,6 +64,36 @@ impl Styles {
         }
     }
 
+    /// The SGR parameter switching the style off. Bold and dimmed share it.
+    pub(crate) fn to_off_str(self) -> &'static str {
+        match self {
+            Styles::Clear => "",
+            Styles::Bold | Styles::Dimmed => "22",
+            Styles::Italic => "23",
+            Styles::Underline => "24",
+            Styles::Blink => "25",
+            Styles::Reversed => "27",
+            Styles::Hidden => "28",
+            Styles::Strikethrough => "29",
+        }
+    }
+
+    /// The name `FromStr` parses back into the same style.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Styles::Clear => "clear",
+            Styles::Bold => "bold",
+            Styles::Dimmed => "dimmed",
+            Styles::Italic => "italic",
+            Styles::Underline => "underline",
+            Styles::Blink => "blink",
+            Styles::Reversed => "reversed",
+            Styles::Hidden => "hidden",
+            Styles::Strikethrough => "strikethrough",
+        }
+    }
+
     fn to_u8(self) -> u8 {
         match self {
             Styles::Clear => CLEARV,
@@ -73,6 +108,7 @@ impl Styles {
         }
     }
 
+    #[cfg(test)]
     fn from_u8(u: u8) -> Option<Vec<Styles>> {
         if u == CLEARV {
             return None;
@@ -80,7 +116,7 @@ impl Styles {
 
         let res: Vec<Styles> = STYLES
             .iter()
-            .filter(|&&(ref mask, _)| (0 != (u & mask)))
+            .filter(|&(mask, _)| 0 != (u & mask))
             .map(|&(_, value)| value)
             .collect();
         if res.is_empty() {
@@ -91,6 +127,27 @@ impl Styles {
     }
 }
 
+impl FromStr for Styles {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let src = src.to_lowercase();
+
+        match src.as_ref() {
+            "clear" | "normal" => Ok(Styles::Clear),
+            "bold" => Ok(Styles::Bold),
+            "dimmed" => Ok(Styles::Dimmed),
+            "italic" => Ok(Styles::Italic),
+            "underline" => Ok(Styles::Underline),
+            "blink" => Ok(Styles::Blink),
+            "reversed" | "reverse" => Ok(Styles::Reversed),
+            "hidden" => Ok(Styles::Hidden),
+            "strikethrough" => Ok(Styles::Strikethrough),
+            _ => Err(()),
+        }
+    }
+}
+
 #[allow(missing_docs)]
 pub static NO_STYLE: Style = Style(CLEARV);
 #[allow(missing_docs)]
@@ -124,13 +181,11 @@ impl Style {
         self.0 & s == s
     }
 
-    pub(crate) fn to_str(self) -> String {
-        let styles = Styles::from_u8(self.0).unwrap_or_default();
-        styles
+    pub(crate) fn iter(self) -> impl Iterator<Item = Styles> {
+        STYLES
             .iter()
-            .map(|s| s.to_str())
-            .collect::<Vec<&str>>()
-            .join(";")
+            .filter(move |&&(mask, _)| 0 != (self.0 & mask))
+            .map(|&(_, value)| value)
     }
 }
 
@@ -204,6 +259,45 @@ impl ops::SubAssign<Style> for Style {
     }
 }
 
+#[cfg(feature = "serde")]
+impl Serialize for Styles {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Styles {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let src = String::deserialize(deserializer)?;
+        src.parse()
+            .map_err(|_| de::Error::custom(format_args!("unknown style `{}`", src)))
+    }
+}
+
+/// A `Style` is written as the list of its `Styles`, e.g. `["bold", "italic"]`.
+#[cfg(feature = "serde")]
+impl Serialize for Style {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let styles: Vec<Styles> = self.iter().collect();
+        let mut seq = serializer.serialize_seq(Some(styles.len()))?;
+        for style in styles {
+            seq.serialize_element(&style)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Style {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let styles = Vec::<Styles>::deserialize(deserializer)?;
+        Ok(styles
+            .into_iter()
+            .fold(NO_STYLE, |style, styles| style + styles))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -370,13 +464,65 @@ mod tests {
         }
     }
 
+    mod styles_from_str {
+        use super::super::Styles;
+
+        #[test]
+        fn parse() {
+            assert_eq!(Ok(Styles::Bold), "bold".parse());
+            assert_eq!(Ok(Styles::Strikethrough), "Strikethrough".parse());
+            assert_eq!(Ok(Styles::Reversed), "reverse".parse());
+            assert_eq!(Ok(Styles::Clear), "normal".parse());
+        }
+
+        #[test]
+        fn error() {
+            let style: Result<Styles, ()> = "boldish".parse();
+            assert_eq!(Err(()), style)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    mod serialize {
+        extern crate serde_json;
+
+        use super::super::Styles::*;
+        use super::super::{Style, Styles, NO_STYLE};
+
+        #[test]
+        fn styles() {
+            assert_eq!("\"strikethrough\"", serde_json::to_string(&Strikethrough).unwrap());
+            assert_eq!(Italic, serde_json::from_str::<Styles>("\"italic\"").unwrap());
+            assert!(serde_json::from_str::<Styles>("\"boldish\"").is_err());
+        }
+
+        #[test]
+        fn style_as_list() {
+            let style = Bold + Underline;
+            assert_eq!(
+                "[\"bold\",\"underline\"]",
+                serde_json::to_string(&style).unwrap()
+            );
+            assert_eq!("[]", serde_json::to_string(&NO_STYLE).unwrap());
+        }
+
+        #[test]
+        fn style_round_trip() {
+            let styles: &[Style] = &[NO_STYLE, Bold.into(), Dimmed + Italic + Hidden];
+            for style in styles {
+                let json = serde_json::to_string(style).unwrap();
+                assert_eq!(*style, serde_json::from_str::<Style>(&json).unwrap());
+            }
+        }
+    }
+
     #[test]
     fn test_style_contains() {
         let mut style = Style(Styles::Bold.to_u8());
         style += Styles::Italic;
 
-        assert_eq!(style.contains(Styles::Bold), true);
-        assert_eq!(style.contains(Styles::Italic), true);
-        assert_eq!(style.contains(Styles::Dimmed), false);
+        assert!(style.contains(Styles::Bold));
+        assert!(style.contains(Styles::Italic));
+        assert!(!style.contains(Styles::Dimmed));
     }
 }
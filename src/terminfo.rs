@@ -0,0 +1,447 @@
+//! Color capabilities read from the compiled terminfo entry of a terminal.
+//!
+//! The entry of `$TERM` is looked up in the same directories as ncurses:
+//! `$TERMINFO`, `~/.terminfo`, `$TERMINFO_DIRS`, then `/etc/terminfo`,
+//! `/lib/terminfo`, `/usr/share/terminfo` and `/usr/lib/terminfo`. Both the
+//! legacy format and the one with 32-bit numbers of ncurses 6.1 are read,
+//! along with their extended capabilities.
+//!
+//! With the `terminfo` feature, [`ShouldColorize::color_level`] uses it when
+//! the environment doesn't tell.
+//!
+//! ```no_run
+//! use colored::terminfo::Terminfo;
+//!
+//! if let Some(terminfo) = Terminfo::from_env() {
+//!     println!("{:?} colors", terminfo.colors());
+//! }
+//! ```
+//!
+//! [`ShouldColorize::color_level`]: crate::control::ShouldColorize::color_level
+
+use std::{convert::TryFrom, env, error::Error, fmt, fs, path::PathBuf};
+
+use control::ColorLevel;
+
+/// The legacy format, with 16-bit numbers.
+const MAGIC_LEGACY: i16 = 0o432;
+/// The format of ncurses 6.1, with 32-bit numbers.
+const MAGIC_32BIT: i16 = 0o1036;
+/// The index of `colors` among the numeric capabilities.
+const COLORS: usize = 13;
+/// The directories searched after the ones of the environment.
+const DEFAULT_DIRS: [&str; 4] = [
+    "/etc/terminfo",
+    "/lib/terminfo",
+    "/usr/share/terminfo",
+    "/usr/lib/terminfo",
+];
+
+/// The color capabilities of a terminfo entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Terminfo {
+    names: Vec<String>,
+    colors: Option<u32>,
+    true_color: bool,
+}
+
+/// The error returned when a terminfo entry can't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseTerminfoError {
+    kind: ErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ErrorKind {
+    BadMagic(i16),
+    BadCount,
+    Truncated,
+}
+
+impl fmt::Display for ParseTerminfoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ErrorKind::BadMagic(magic) => {
+                write!(
+                    f,
+                    "not a compiled terminfo entry, magic number {:#o}",
+                    magic
+                )
+            }
+            ErrorKind::BadCount => f.write_str("negative size in the terminfo header"),
+            ErrorKind::Truncated => f.write_str("truncated terminfo entry"),
+        }
+    }
+}
+
+impl Error for ParseTerminfoError {}
+
+impl Terminfo {
+    /// Load the entry of the terminal named by `$TERM`.
+    ///
+    /// Returns `None` if `$TERM` isn't set, or its entry isn't found or can't
+    /// be parsed.
+    pub fn from_env() -> Option<Terminfo> {
+        Terminfo::load_with(&env::var("TERM").ok()?, |name| env::var(name).ok())
+    }
+
+    /// Load the entry of the terminal `term`, such as `xterm-256color`.
+    ///
+    /// Returns `None` if it isn't found or can't be parsed.
+    pub fn load(term: &str) -> Option<Terminfo> {
+        Terminfo::load_with(term, |name| env::var(name).ok())
+    }
+
+    /// Load the entry of `term`, with the environment variables of the
+    /// search path looked up by `env`.
+    pub(crate) fn load_with<E: Fn(&str) -> Option<String>>(term: &str, env: E) -> Option<Terminfo> {
+        let first = term.chars().next()?;
+        if term.contains('/') || term.starts_with('.') {
+            return None;
+        }
+        // the entries are in a directory named after their first letter, or
+        // its hex code on case-insensitive file systems
+        let subdirs = [first.to_string(), format!("{:x}", first as u32)];
+
+        search_dirs(env)
+            .into_iter()
+            .flat_map(|dir| {
+                subdirs
+                    .iter()
+                    .map(move |subdir| dir.join(subdir).join(term))
+            })
+            .filter_map(|path| fs::read(path).ok())
+            .find_map(|bytes| Terminfo::parse(&bytes).ok())
+    }
+
+    /// Parse a compiled terminfo entry.
+    pub fn parse(bytes: &[u8]) -> Result<Terminfo, ParseTerminfoError> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        let magic = reader.i16()?;
+        let number_size = match magic {
+            MAGIC_LEGACY => 2,
+            MAGIC_32BIT => 4,
+            _ => {
+                return Err(ParseTerminfoError {
+                    kind: ErrorKind::BadMagic(magic),
+                })
+            }
+        };
+        let names_size = reader.count()?;
+        let bools = reader.count()?;
+        let numbers = reader.count()?;
+        let strings = reader.count()?;
+        let table_size = reader.count()?;
+
+        let names = reader.take(names_size)?;
+        let names = String::from_utf8_lossy(names.split(|&b| b == 0).next().unwrap_or(&[]));
+        let mut terminfo = Terminfo {
+            names: names.split('|').map(String::from).collect(),
+            colors: None,
+            true_color: false,
+        };
+
+        reader.take(bools)?;
+        reader.align();
+        for idx in 0..numbers {
+            let number = reader.number(number_size)?;
+            if idx == COLORS && number >= 0 {
+                terminfo.colors = Some(number as u32);
+            }
+        }
+        reader.take(strings * 2)?;
+        reader.take(table_size)?;
+
+        reader.align();
+        if reader.pos < bytes.len() {
+            terminfo.true_color = extended_true_color(&mut reader, number_size)?;
+        }
+        Ok(terminfo)
+    }
+
+    /// The names of the terminal, the first one being the one of its file.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The number of colors, from the `colors` capability.
+    pub fn colors(&self) -> Option<u32> {
+        self.colors
+    }
+
+    /// Checks if the terminal supports 24-bit colors, from the `RGB` or `Tc`
+    /// extended capabilities.
+    pub fn has_true_color(&self) -> bool {
+        self.true_color
+    }
+
+    /// The colors the terminal supports, or `None` if it has less than the 8
+    /// basic ones.
+    pub fn color_level(&self) -> Option<ColorLevel> {
+        match self.colors {
+            _ if self.true_color => Some(ColorLevel::TrueColor),
+            Some(colors) if colors >= 0x100_0000 => Some(ColorLevel::TrueColor),
+            Some(colors) if colors >= 256 => Some(ColorLevel::Ansi256),
+            Some(colors) if colors >= 8 => Some(ColorLevel::Basic),
+            _ => None,
+        }
+    }
+}
+
+/// The directories to look for entries into, in order.
+fn search_dirs<E: Fn(&str) -> Option<String>>(env: E) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = env("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = env("HOME") {
+        dirs.push(PathBuf::from(home).join(".terminfo"));
+    }
+    match env("TERMINFO_DIRS") {
+        Some(terminfo_dirs) => {
+            for dir in terminfo_dirs.split(':') {
+                // an empty entry stands for the default directories
+                if dir.is_empty() {
+                    dirs.extend(DEFAULT_DIRS.iter().map(PathBuf::from));
+                } else {
+                    dirs.push(PathBuf::from(dir));
+                }
+            }
+        }
+        None => dirs.extend(DEFAULT_DIRS.iter().map(PathBuf::from)),
+    }
+    dirs
+}
+
+/// Read the extended capabilities, which come with their names, and checks
+/// if `RGB` or `Tc` is among them.
+fn extended_true_color(
+    reader: &mut Reader,
+    number_size: usize,
+) -> Result<bool, ParseTerminfoError> {
+    let bools = reader.count()?;
+    let numbers = reader.count()?;
+    let strings = reader.count()?;
+    let _offsets = reader.count()?;
+    let table_size = reader.count()?;
+
+    let bool_values = reader.take(bools)?.to_vec();
+    reader.align();
+    let mut number_values = Vec::with_capacity(numbers);
+    for _ in 0..numbers {
+        number_values.push(reader.number(number_size)?);
+    }
+    let mut string_offsets = Vec::with_capacity(strings);
+    for _ in 0..strings {
+        string_offsets.push(reader.i16()?);
+    }
+    let mut name_offsets = Vec::with_capacity(bools + numbers + strings);
+    for _ in 0..bools + numbers + strings {
+        name_offsets.push(reader.i16()?);
+    }
+    let table = reader.take(table_size)?;
+
+    // the names come after the values of the strings in the table
+    let names_start = string_offsets
+        .iter()
+        .filter(|&&offset| offset >= 0)
+        .filter_map(|&offset| {
+            let value = table.get(offset as usize..)?;
+            let len = value.iter().position(|&b| b == 0)?;
+            Some(offset as usize + len + 1)
+        })
+        .max()
+        .unwrap_or(0);
+    let name = |idx: usize| -> Option<&[u8]> {
+        let offset = *name_offsets.get(idx)?;
+        let name = table.get(names_start + usize::try_from(offset).ok()?..)?;
+        name.split(|&b| b == 0).next()
+    };
+    let is_true_color = |idx: usize| matches!(name(idx), Some(b"RGB") | Some(b"Tc"));
+
+    let bool_set = (0..bools).any(|idx| bool_values[idx] == 1 && is_true_color(idx));
+    let number_set = (0..numbers).any(|idx| number_values[idx] > 0 && is_true_color(bools + idx));
+    let string_set =
+        (0..strings).any(|idx| string_offsets[idx] >= 0 && is_true_color(bools + numbers + idx));
+    Ok(bool_set || number_set || string_set)
+}
+
+/// Reads the little-endian values of a compiled entry.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ParseTerminfoError> {
+        let taken = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or(ParseTerminfoError {
+                kind: ErrorKind::Truncated,
+            })?;
+        self.pos += len;
+        Ok(taken)
+    }
+
+    /// Skip the padding byte which makes the next value start at an even
+    /// offset.
+    fn align(&mut self) {
+        if self.pos % 2 == 1 {
+            self.pos += 1;
+        }
+    }
+
+    fn i16(&mut self) -> Result<i16, ParseTerminfoError> {
+        let bytes = self.take(2)?;
+        Ok(i16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn number(&mut self, size: usize) -> Result<i32, ParseTerminfoError> {
+        if size == 2 {
+            return self.i16().map(i32::from);
+        }
+        let bytes = self.take(4)?;
+        Ok(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// A size or a count in a header.
+    fn count(&mut self) -> Result<usize, ParseTerminfoError> {
+        usize::try_from(self.i16()?).map_err(|_| ParseTerminfoError {
+            kind: ErrorKind::BadCount,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use control::{ShouldColorize, Stream};
+
+    fn fixtures() -> String {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/terminfo").to_string()
+    }
+
+    fn load(term: &str) -> Option<Terminfo> {
+        Terminfo::load_with(term, |name| {
+            if name == "TERMINFO_DIRS" {
+                Some(fixtures())
+            } else {
+                None
+            }
+        })
+    }
+
+    #[test]
+    fn legacy_format() {
+        let terminfo = load("colored-16").unwrap();
+        assert_eq!(
+            ["colored-16", "test entry with 16 colors"],
+            terminfo.names()
+        );
+        assert_eq!(Some(16), terminfo.colors());
+        assert!(!terminfo.has_true_color());
+        assert_eq!(Some(ColorLevel::Basic), terminfo.color_level());
+
+        let terminfo = load("xterm-256color").unwrap();
+        assert_eq!(Some(256), terminfo.colors());
+        assert_eq!(Some(ColorLevel::Ansi256), terminfo.color_level());
+    }
+
+    #[test]
+    fn format_with_32bit_numbers() {
+        let terminfo = load("colored-rgb").unwrap();
+        assert_eq!(Some(0x100_0000), terminfo.colors());
+        assert!(terminfo.has_true_color());
+    }
+
+    #[test]
+    fn extended_capabilities() {
+        let terminfo = load("colored-truecolor").unwrap();
+        assert_eq!(Some(256), terminfo.colors());
+        assert!(terminfo.has_true_color());
+        assert_eq!(Some(ColorLevel::TrueColor), terminfo.color_level());
+    }
+
+    #[test]
+    fn without_colors() {
+        let terminfo = load("colored-mono").unwrap();
+        assert_eq!(None, terminfo.colors());
+        assert_eq!(None, terminfo.color_level());
+    }
+
+    #[test]
+    fn not_found() {
+        assert_eq!(None, load("colored-missing"));
+        assert_eq!(None, load("../terminfo/c/colored-16"));
+        assert_eq!(None, load(""));
+    }
+
+    #[test]
+    fn search_order() {
+        let dirs = search_dirs(|name| match name {
+            "TERMINFO" => Some("/opt/terminfo".to_string()),
+            "HOME" => Some("/home/me".to_string()),
+            "TERMINFO_DIRS" => Some("/a::/b".to_string()),
+            _ => None,
+        });
+        let dirs: Vec<_> = dirs.iter().map(|dir| dir.to_str().unwrap()).collect();
+        assert_eq!(
+            vec![
+                "/opt/terminfo",
+                "/home/me/.terminfo",
+                "/a",
+                "/etc/terminfo",
+                "/lib/terminfo",
+                "/usr/share/terminfo",
+                "/usr/lib/terminfo",
+                "/b",
+            ],
+            dirs
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = Terminfo::parse(b"\x1E\x03").unwrap_err();
+        assert_eq!(
+            "not a compiled terminfo entry, magic number 0o1436",
+            err.to_string()
+        );
+
+        let entry = fs::read(fixtures() + "/c/colored-truecolor").unwrap();
+        let err = Terminfo::parse(&entry[..entry.len() - 1]).unwrap_err();
+        assert_eq!("truncated terminfo entry", err.to_string());
+    }
+
+    #[test]
+    fn color_level_of_should_colorize() {
+        let colorize_control = ShouldColorize::from_sources(
+            |name| match name {
+                "TERM" => Some("xterm-256color".to_string()),
+                "TERMINFO" => Some(fixtures()),
+                _ => None,
+            },
+            |_| true,
+        );
+        assert_eq!(
+            Some(ColorLevel::Ansi256),
+            colorize_control.color_level(Stream::Stdout)
+        );
+
+        let colorize_control = ShouldColorize::from_sources(
+            |name| match name {
+                "TERM" => Some("xterm-256color".to_string()),
+                "TERMINFO" => Some(fixtures()),
+                "FORCE_COLOR" => Some("3".to_string()),
+                _ => None,
+            },
+            |_| true,
+        );
+        assert_eq!(
+            Some(ColorLevel::TrueColor),
+            colorize_control.color_level(Stream::Stdout)
+        );
+    }
+}
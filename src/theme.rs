@@ -0,0 +1,449 @@
+//! Named, semantic styles such as `error` or `path`, kept out of the code.
+//!
+//! A [`Theme`] maps names to [`StyleSpec`]s. Specs are written as short,
+//! human-editable strings like `"bold red"` or `"underline cyan on black"`,
+//! so a theme can live in a configuration file and be loaded with `serde`
+//! (behind the `serde` feature).
+//!
+//! ```rust
+//! use colored::*;
+//! use colored::theme::Theme;
+//!
+//! let mut theme = Theme::new();
+//! theme.insert("error", "bold red".parse().unwrap());
+//! theme.insert("path", "underline cyan".parse().unwrap());
+//!
+//! assert_eq!("oops".themed_with("error", &theme), "oops".red().bold());
+//! // unknown names leave the text as it is
+//! assert_eq!("fine".themed_with("unknown", &theme), "fine".normal());
+//! ```
+
+use std::{borrow::Cow, collections::HashMap, error::Error, fmt, str::FromStr, sync::RwLock};
+
+use color::Color;
+use style::{Style, Styles, NO_STYLE};
+use {ColoredString, Colorize};
+
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The colors and styles attached to a name of a [`Theme`].
+///
+/// Parsed from a whitespace separated list of words: style names (`bold`,
+/// `italic`, ...), a foreground color, and a background color introduced by
+/// `on`. Colors are any name accepted by [`Color`]'s `FromStr`, including
+/// `bright <color>` and `#rrggbb`.
+///
+/// ```rust
+/// # use colored::*;
+/// # use colored::theme::StyleSpec;
+/// let spec: StyleSpec = "bold bright red on #202020".parse().unwrap();
+/// assert_eq!(spec.fgcolor(), Some(Color::BrightRed));
+/// assert_eq!(spec.bgcolor(), Some(Color::TrueColor { r: 32, g: 32, b: 32 }));
+/// assert_eq!(spec.style().contains(Styles::Bold), true);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StyleSpec {
+    fgcolor: Option<Color>,
+    bgcolor: Option<Color>,
+    style: Style,
+}
+
+impl Default for StyleSpec {
+    fn default() -> Self {
+        StyleSpec {
+            fgcolor: None,
+            bgcolor: None,
+            style: NO_STYLE,
+        }
+    }
+}
+
+impl StyleSpec {
+    /// A spec that applies nothing.
+    pub fn new() -> Self {
+        StyleSpec::default()
+    }
+
+    /// Set the foreground color.
+    pub fn fg<S: Into<Color>>(mut self, color: S) -> Self {
+        self.fgcolor = Some(color.into());
+        self
+    }
+
+    /// Set the background color.
+    pub fn bg<S: Into<Color>>(mut self, color: S) -> Self {
+        self.bgcolor = Some(color.into());
+        self
+    }
+
+    /// Add a style on top of the current ones.
+    pub fn add_style<S: Into<Style>>(mut self, style: S) -> Self {
+        self.style += style.into();
+        self
+    }
+
+    /// Get the foreground color, if any.
+    pub fn fgcolor(&self) -> Option<Color> {
+        self.fgcolor
+    }
+
+    /// Get the background color, if any.
+    pub fn bgcolor(&self) -> Option<Color> {
+        self.bgcolor
+    }
+
+    /// Get the styles.
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    /// Apply the spec to anything that can be colorized. Colors that the
+    /// spec doesn't set are left untouched, styles are added.
+    pub fn apply<C: Colorize>(&self, target: C) -> ColoredString {
+        let mut res = target.add_style(self.style);
+        if let Some(fgcolor) = self.fgcolor {
+            res = res.color(fgcolor);
+        }
+        if let Some(bgcolor) = self.bgcolor {
+            res = res.on_color(bgcolor);
+        }
+        res
+    }
+}
+
+/// Writes the spec back in the form `FromStr` accepts.
+///
+/// ```rust
+/// # use colored::*;
+/// # use colored::theme::StyleSpec;
+/// let spec = StyleSpec::new().add_style(Styles::Bold).fg(Color::Red).bg("bright black");
+/// assert_eq!(spec.to_string(), "bold red on bright black");
+/// ```
+impl fmt::Display for StyleSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let styles = self.style.iter().map(|style| Cow::from(style.name()));
+        let fgcolor = self.fgcolor.map(Color::to_name);
+        let bgcolor = self.bgcolor.map(|color| format!("on {}", color.to_name()).into());
+
+        let words: Vec<Cow<str>> = styles.chain(fgcolor).chain(bgcolor).collect();
+        f.write_str(&words.join(" "))
+    }
+}
+
+/// The error returned when a [`StyleSpec`] can't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseStyleSpecError {
+    word: String,
+}
+
+impl fmt::Display for ParseStyleSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.word.is_empty() {
+            f.write_str("expected a color after `on` or `bright`")
+        } else {
+            write!(f, "unknown color or style `{}`", self.word)
+        }
+    }
+}
+
+impl Error for ParseStyleSpecError {}
+
+impl FromStr for StyleSpec {
+    type Err = ParseStyleSpecError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let mut spec = StyleSpec::default();
+        let mut words = src.split_whitespace();
+
+        while let Some(word) = words.next() {
+            let background = word.eq_ignore_ascii_case("on");
+            let word = if background { words.next() } else { Some(word) };
+            let word = match word {
+                Some(word) if word.eq_ignore_ascii_case("bright") => {
+                    words.next().map(|color| format!("bright {}", color))
+                }
+                word => word.map(String::from),
+            };
+            let word = word.ok_or_else(|| ParseStyleSpecError {
+                word: String::new(),
+            })?;
+
+            if !background {
+                if let Ok(style) = word.parse::<Styles>() {
+                    spec.style += style;
+                    continue;
+                }
+            }
+
+            let color = word
+                .parse::<Color>()
+                .map_err(|_| ParseStyleSpecError { word })?;
+            if background {
+                spec.bgcolor = Some(color);
+            } else {
+                spec.fgcolor = Some(color);
+            }
+        }
+
+        Ok(spec)
+    }
+}
+
+/// A set of named [`StyleSpec`]s.
+///
+/// Use [`Colorize::themed_with`] to style text against a given theme, or
+/// [`set_theme`] and [`Colorize::themed`] to use a process-wide one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Theme {
+    styles: HashMap<String, StyleSpec>,
+}
+
+impl Theme {
+    /// An empty theme.
+    pub fn new() -> Self {
+        Theme::default()
+    }
+
+    /// Attach a spec to a name, returning the spec it replaces, if any.
+    pub fn insert<S: Into<String>>(&mut self, name: S, spec: StyleSpec) -> Option<StyleSpec> {
+        self.styles.insert(name.into(), spec)
+    }
+
+    /// Get the spec attached to a name.
+    pub fn get(&self, name: &str) -> Option<&StyleSpec> {
+        self.styles.get(name)
+    }
+
+    /// Apply the spec attached to `name` to `target`. If the name is unknown,
+    /// `target` keeps its current colors and styles.
+    pub fn apply<C: Colorize>(&self, name: &str, target: C) -> ColoredString {
+        self.get(name).copied().unwrap_or_default().apply(target)
+    }
+}
+
+impl<S: Into<String>> std::iter::FromIterator<(S, StyleSpec)> for Theme {
+    fn from_iter<I: IntoIterator<Item = (S, StyleSpec)>>(iter: I) -> Self {
+        Theme {
+            styles: iter
+                .into_iter()
+                .map(|(name, spec)| (name.into(), spec))
+                .collect(),
+        }
+    }
+}
+
+lazy_static! {
+    static ref THEME: RwLock<Theme> = RwLock::new(Theme::default());
+}
+
+/// Replace the process-wide theme used by [`Colorize::themed`].
+pub fn set_theme(theme: Theme) {
+    *THEME.write().unwrap_or_else(|e| e.into_inner()) = theme;
+}
+
+pub(crate) fn apply_global<C: Colorize>(name: &str, target: C) -> ColoredString {
+    THEME
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .apply(name, target)
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for StyleSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let src = String::deserialize(deserializer)?;
+        src.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for StyleSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Theme {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.styles.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Theme {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        HashMap::deserialize(deserializer).map(|styles| Theme { styles })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod style_spec_from_str {
+        use super::*;
+
+        #[test]
+        fn empty() {
+            assert_eq!(Ok(StyleSpec::new()), "".parse());
+        }
+
+        #[test]
+        fn styles_and_colors() {
+            let spec: StyleSpec = "bold underline red on blue".parse().unwrap();
+            assert_eq!(
+                StyleSpec::new()
+                    .add_style(Styles::Bold)
+                    .add_style(Styles::Underline)
+                    .fg(Color::Red)
+                    .bg(Color::Blue),
+                spec
+            );
+        }
+
+        #[test]
+        fn bright_and_hex_colors() {
+            let spec: StyleSpec = "Bright Green on #102030".parse().unwrap();
+            assert_eq!(
+                StyleSpec::new()
+                    .fg(Color::BrightGreen)
+                    .bg(Color::TrueColor { r: 16, g: 32, b: 48 }),
+                spec
+            );
+        }
+
+        #[test]
+        fn unknown_word() {
+            let err = "bold reddish".parse::<StyleSpec>().unwrap_err();
+            assert_eq!("unknown color or style `reddish`", err.to_string());
+        }
+
+        #[test]
+        fn style_as_background() {
+            assert!("on bold".parse::<StyleSpec>().is_err());
+        }
+
+        #[test]
+        fn display_round_trip() {
+            let specs = [
+                "",
+                "italic",
+                "bold underline bright red",
+                "on #0a0b0c",
+                "dimmed white on bright black",
+            ];
+            for src in specs.iter() {
+                let spec: StyleSpec = src.parse().unwrap();
+                assert_eq!(*src, spec.to_string());
+            }
+        }
+
+        #[test]
+        fn dangling_on() {
+            let err = "red on".parse::<StyleSpec>().unwrap_err();
+            assert_eq!("expected a color after `on` or `bright`", err.to_string());
+        }
+    }
+
+    mod theme {
+        use super::*;
+
+        fn theme() -> Theme {
+            vec![
+                ("error", "bold red".parse().unwrap()),
+                ("highlight", "on yellow".parse().unwrap()),
+            ]
+            .into_iter()
+            .collect()
+        }
+
+        #[test]
+        fn themed_with() {
+            let theme = theme();
+            assert_eq!("oops".red().bold(), "oops".themed_with("error", &theme));
+            assert_eq!(
+                "here".blue().on_yellow(),
+                "here".blue().themed_with("highlight", &theme)
+            );
+        }
+
+        #[test]
+        fn missing_key_keeps_input() {
+            let theme = theme();
+            assert_eq!("plain".normal(), "plain".themed_with("warning", &theme));
+            assert_eq!(
+                "still blue".blue(),
+                "still blue".blue().themed_with("warning", &theme)
+            );
+        }
+
+        #[test]
+        fn global_theme() {
+            set_theme(theme());
+            assert_eq!("oops".red().bold(), "oops".themed("error"));
+            assert_eq!("fine".normal(), "fine".themed("warning"));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    mod deserialize {
+        extern crate serde_json;
+        extern crate toml;
+
+        use super::*;
+
+        #[test]
+        fn from_toml() {
+            let theme: Theme = toml::from_str(
+                r##"
+                error = "bold red"
+                warning = "yellow"
+                path = "underline bright cyan on #000000"
+                "##,
+            )
+            .unwrap();
+
+            assert_eq!(Some(&"bold red".parse().unwrap()), theme.get("error"));
+            assert_eq!(Some(&StyleSpec::new().fg(Color::Yellow)), theme.get("warning"));
+            assert_eq!(
+                Some(
+                    &StyleSpec::new()
+                        .add_style(Styles::Underline)
+                        .fg(Color::BrightCyan)
+                        .bg(Color::TrueColor { r: 0, g: 0, b: 0 })
+                ),
+                theme.get("path")
+            );
+        }
+
+        #[test]
+        fn from_json() {
+            let theme: Theme =
+                serde_json::from_str(r#"{ "error": "bold red", "highlight": "on yellow" }"#)
+                    .unwrap();
+
+            assert_eq!("oops".red().bold(), "oops".themed_with("error", &theme));
+        }
+
+        #[test]
+        fn round_trip() {
+            let theme: Theme = vec![
+                ("error", "bold red".parse().unwrap()),
+                ("path", "underline cyan on #000000".parse().unwrap()),
+            ]
+            .into_iter()
+            .collect();
+
+            let json = serde_json::to_string(&theme).unwrap();
+            assert_eq!(theme, serde_json::from_str(&json).unwrap());
+        }
+
+        #[test]
+        fn invalid_spec() {
+            let err = serde_json::from_str::<Theme>(r#"{ "error": "bold crimson" }"#).unwrap_err();
+            assert!(err.to_string().contains("unknown color or style `crimson`"));
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    fmt,
+    io::{self, IsTerminal, Write},
+};
+
+use ColoredString;
+
+/// A writer that colors what it writes if it is a terminal.
+///
+/// Stdout and stderr are already handled by [`SHOULD_COLORIZE`](crate::control::SHOULD_COLORIZE);
+/// wrap any other writer, such as a `File` or a `TcpStream`, to decide
+/// whether to write the colors of [`ColoredString`]s depending on that
+/// writer instead. The environment variables and overrides of
+/// [`control`](crate::control) still apply.
+///
+/// ```no_run
+/// # use colored::*;
+/// use std::io::Write;
+///
+/// let file = std::fs::File::create("build.log")?;
+/// let mut writer = ColorWriter::new(file);
+/// writer.write_colored(&"warning".yellow())?;
+/// writeln!(writer, ": unused variable")?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct ColorWriter<W> {
+    inner: W,
+    colorize: bool,
+}
+
+impl<W: Write + IsTerminal> ColorWriter<W> {
+    /// Wrap `inner`, which gets colors if it is a terminal.
+    pub fn new(inner: W) -> Self {
+        let colorize = ::should_colorize_for_writer(&inner);
+        ColorWriter { inner, colorize }
+    }
+}
+
+impl<W: Write> ColorWriter<W> {
+    /// Wrap `inner`, which gets colors depending on `colorize` only.
+    pub fn with_colors(inner: W, colorize: bool) -> Self {
+        ColorWriter {
+            inner,
+            colorize: colorize && cfg!(not(feature = "no-color")),
+        }
+    }
+
+    /// Returns if the colors are written.
+    pub fn should_colorize(&self) -> bool {
+        self.colorize
+    }
+
+    /// Write `s`, with its colors if they should be written.
+    pub fn write_colored(&mut self, s: &ColoredString) -> io::Result<()> {
+        write!(
+            self.inner,
+            "{}",
+            WithColors {
+                string: s,
+                colorize: self.colorize,
+            }
+        )
+    }
+
+    /// Get a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the wrapped writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwrap the writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for ColorWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.inner.write_all(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Write `s` to `writer`, with its colors if `writer` is a terminal. See
+/// [`ColorWriter`].
+///
+/// ```rust
+/// # use colored::*;
+/// let mut stderr = std::io::stderr();
+/// colored::write_to(&mut stderr, &"done\n".green())?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn write_to<W: Write + IsTerminal>(writer: &mut W, s: &ColoredString) -> io::Result<()> {
+    let colorize = ::should_colorize_for_writer(writer);
+    ColorWriter::with_colors(writer, colorize).write_colored(s)
+}
+
+struct WithColors<'a> {
+    string: &'a ColoredString,
+    colorize: bool,
+}
+
+impl<'a> fmt::Display for WithColors<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let string = self.string;
+        ::fmt_colored(
+            f,
+            self.colorize,
+            &string.input,
+            string.fgcolor,
+            string.bgcolor,
+            string.style,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Colorize;
+
+    #[cfg_attr(feature = "no-color", ignore)]
+    #[test]
+    fn with_colors() {
+        let mut writer = ColorWriter::with_colors(Vec::new(), true);
+        writer.write_colored(&"red".red()).unwrap();
+        writer.write_all(b" plain").unwrap();
+        assert!(writer.should_colorize());
+        assert_eq!(b"\x1B[31mred\x1B[0m plain", &writer.into_inner()[..]);
+    }
+
+    #[test]
+    fn without_colors() {
+        let mut writer = ColorWriter::with_colors(Vec::new(), false);
+        writer.write_colored(&"red".red().bold()).unwrap();
+        write!(writer, " {}", 42).unwrap();
+        assert_eq!(b"red 42", &writer.get_ref()[..]);
+    }
+
+    #[test]
+    fn same_decision_as_should_colorize() {
+        let name = format!("colored-color-writer-test-{}", ::std::process::id());
+        let path = ::std::env::temp_dir().join(name);
+        let mut file = ::std::fs::File::create(&path).unwrap();
+        let colorize =
+            ::control::SHOULD_COLORIZE.for_writer(&file) && cfg!(not(feature = "no-color"));
+        let writer = ColorWriter::new(file.try_clone().unwrap());
+        assert_eq!(colorize, writer.should_colorize());
+
+        write_to(&mut file, &"blue".blue()).unwrap();
+        drop(file);
+
+        let written = ::std::fs::read_to_string(&path).unwrap();
+        ::std::fs::remove_file(&path).unwrap();
+        let expected = if colorize { "\x1B[34mblue\x1B[0m" } else { "blue" };
+        assert_eq!(expected, written);
+    }
+}
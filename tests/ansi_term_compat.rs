@@ -1,4 +1,4 @@
-#![cfg(not(feature = "no-color"))]
+#![cfg(all(feature = "std", not(feature = "no-color")))]
 #![allow(unused_imports)]
 
 extern crate ansi_term;
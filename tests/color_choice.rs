@@ -0,0 +1,44 @@
+//! `ColorChoice::apply` changes the global override, so it is tested in its
+//! own process.
+
+#![cfg(feature = "std")]
+
+#[cfg(feature = "clap")]
+extern crate clap;
+extern crate colored;
+
+use colored::control::{self, ColorChoice, SHOULD_COLORIZE};
+use colored::Colorize;
+
+#[test]
+fn apply() {
+    ColorChoice::Never.apply();
+    assert!(!SHOULD_COLORIZE.should_colorize());
+    assert_eq!("red", "red".red().to_string());
+
+    ColorChoice::Always.apply();
+    assert!(SHOULD_COLORIZE.should_colorize());
+
+    control::unset_override();
+    let auto = SHOULD_COLORIZE.should_colorize();
+    ColorChoice::Never.apply();
+    ColorChoice::Auto.apply();
+    assert_eq!(auto, SHOULD_COLORIZE.should_colorize());
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn clap_arg() {
+    let command = clap::Command::new("app").arg(ColorChoice::arg());
+    let choice = |args: &[&str]| {
+        let matches = command.clone().try_get_matches_from(args).unwrap();
+        *matches.get_one::<ColorChoice>("color").unwrap()
+    };
+
+    assert_eq!(ColorChoice::Auto, choice(&["app"]));
+    assert_eq!(ColorChoice::Always, choice(&["app", "--color"]));
+    assert_eq!(ColorChoice::Never, choice(&["app", "--color=never"]));
+    assert!(command
+        .try_get_matches_from(["app", "--color=sometimes"])
+        .is_err());
+}
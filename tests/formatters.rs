@@ -0,0 +1,63 @@
+//! `formatters::set_formatter` changes how every colored string is displayed,
+//! so it is tested in its own process.
+
+#![cfg(all(feature = "std", not(feature = "no-color")))]
+
+extern crate colored;
+
+use colored::control;
+use colored::formatters::{self, ColoringFormatter, Html};
+use colored::{Color, Colorize, Paint, Style};
+use std::fmt;
+
+/// Writes the foreground color in brackets around the input.
+struct Brackets;
+
+impl ColoringFormatter for Brackets {
+    fn format(
+        &self,
+        out: &mut dyn fmt::Write,
+        input: &str,
+        fgcolor: Option<Color>,
+        _bgcolor: Option<Color>,
+        _style: Style,
+    ) -> fmt::Result {
+        match fgcolor {
+            Some(color) => write!(out, "[{:?}]{}[/]", color, input),
+            None => out.write_str(input),
+        }
+    }
+}
+
+#[test]
+fn set_formatter_applies_to_every_string() {
+    control::set_override(true);
+
+    formatters::set_formatter(Brackets);
+    assert_eq!("[Red]a[/]", "a".red().to_string());
+    assert_eq!("[Red] a[/]", format!("{:>2}", "a".red()));
+    assert_eq!("[Red]a[/][Blue]b[/]", ("a".red() + "b".blue()).to_string());
+    assert_eq!("[Red]1.50[/]", format!("{:.2}", 1.5f64.painted().red()));
+    assert_eq!("[Red]   1f[/]", format!("{:>5x}", 31u8.painted().red()));
+    assert_eq!(
+        "[Red]+0x0001f[/]",
+        format!("{:+#08x}", 31u8.painted().red())
+    );
+    assert_eq!("a", "a".painted().to_string());
+    assert_eq!(
+        r#"<span style="color:#cd0000">a</span>"#,
+        "a".red().format_with(&Html).to_string()
+    );
+
+    // without colors, the formatter still writes the text
+    formatters::set_formatter(Html);
+    control::set_override(false);
+    assert_eq!("a&amp;b", "a&b".red().to_string());
+    assert_eq!("a&amp;b", "a&b".painted().red().to_string());
+
+    formatters::reset_formatter();
+    control::set_override(true);
+    assert_eq!("\x1B[31ma\x1B[0m", "a".red().to_string());
+    assert_eq!("\x1B[31ma\x1B[0m", "a".painted().red().to_string());
+    control::unset_override();
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "macros")]
+
+#[macro_use]
+extern crate colored;
+
+use colored::Colorize;
+
+#[test]
+fn same_output_as_colorize() {
+    colored::control::with_colors(true, || {
+        assert_eq!(
+            "\x1B[1;31merror\x1B[0m: oops",
+            cformat!("<red><bold>error</bold></red>: oops")
+        );
+        assert_eq!(
+            format!("{}: oops", "error".red().bold()),
+            cformat!("<red><bold>error</bold></red>: oops")
+        );
+        assert_eq!(
+            "x".bright_cyan()
+                .on_truecolor(255, 128, 0)
+                .italic()
+                .to_string(),
+            cformat!("<italic><on_#ff8000><bright_cyan>x</bright_cyan></on_#ff8000></italic>")
+        );
+    });
+}
+
+#[test]
+fn format_arguments() {
+    colored::control::with_colors(true, || {
+        let name = "world";
+        assert_eq!(
+            format!("hello {}!", name.blue()),
+            cformat!("hello <blue>{}</blue>!", name)
+        );
+        assert_eq!(
+            format!("{}", "   world".blue()),
+            cformat!("<blue>{name:>8}</blue>")
+        );
+        assert_eq!(
+            format!("{}", "world  ".blue()),
+            cformat!("<blue>{:<7}</blue>", name)
+        );
+    });
+}
+
+#[test]
+fn colored_arguments() {
+    colored::control::with_colors(true, || {
+        assert_eq!(
+            "\x1B[31m\x1B[34mx\x1B[0m\x1B[31m rest\x1B[0m",
+            cformat!("<red>{} rest</red>", "x".blue())
+        );
+        assert_eq!(
+            format!("{}", format!("{} rest", "x".blue()).red()),
+            cformat!("<red>{} rest</red>", "x".blue())
+        );
+    });
+}
+
+#[test]
+fn spec_tags() {
+    colored::control::with_colors(true, || {
+        assert_eq!(
+            "x".bold().red().on_black().to_string(),
+            cformat!("<bold red on black>x</bold red on black>")
+        );
+        assert_eq!(
+            "x".italic()
+                .bright_cyan()
+                .on_truecolor(255, 128, 0)
+                .to_string(),
+            cformat!("<italic_bright_cyan_on_#ff8000>x</italic_bright_cyan_on_#ff8000>")
+        );
+        assert_eq!(
+            "\x1B[7;35mx\x1B[0m",
+            cformat!("<reverse><Purple>x</Purple></reverse>")
+        );
+    });
+}
+
+#[test]
+fn same_tags_as_markup() {
+    colored::control::with_colors(true, || {
+        // each span on its own, as the macros don't merge them
+        fn markup(template: &str) -> String {
+            let spans = colored::markup::parse(template).unwrap();
+            spans.iter().map(ToString::to_string).collect()
+        }
+
+        assert_eq!(
+            markup("<bold red on black>x</bold red on black>"),
+            cformat!("<bold red on black>x</bold red on black>")
+        );
+        assert_eq!(
+            markup("<italic_bright_cyan_on_#ff8000>x</italic_bright_cyan_on_#ff8000>"),
+            cformat!("<italic_bright_cyan_on_#ff8000>x</italic_bright_cyan_on_#ff8000>")
+        );
+        assert_eq!(
+            markup("<reverse><Purple>x</Purple></reverse>"),
+            cformat!("<reverse><Purple>x</Purple></reverse>")
+        );
+        assert_eq!(
+            markup("<red>a<on_blue><bold>b</bold></on_blue>c</red>"),
+            cformat!("<red>a<on_blue><bold>b</bold></on_blue>c</red>")
+        );
+    });
+}
+
+#[test]
+fn escaping() {
+    colored::control::with_colors(true, || {
+        assert_eq!(
+            format!("a < b {}", "<".green()),
+            cformat!("a << b <green><<</green>")
+        );
+        assert_eq!("{<}", cformat!("{{<<}}"));
+    });
+}
+
+#[test]
+fn printing() {
+    cprint!("<red>cprint</red> ");
+    cprintln!("<green>cprintln</green> {}", 1);
+    ceprint!("<red>ceprint</red> ");
+    ceprintln!("<yellow>ceprintln</yellow> {}", 2);
+    cprintln!();
+}
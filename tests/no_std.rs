@@ -0,0 +1,30 @@
+//! Without the `std` feature, only the override decides whether to colorize.
+
+#![cfg(all(not(feature = "std"), not(feature = "no-color")))]
+
+extern crate colored;
+
+use colored::control::{self, Stream, SHOULD_COLORIZE};
+use colored::Colorize;
+
+#[test]
+fn only_the_override_colorizes() {
+    assert!(!SHOULD_COLORIZE.should_colorize());
+    assert_eq!("red", "red".red().to_string());
+
+    control::set_override(true);
+    assert!(SHOULD_COLORIZE.for_stream(Stream::Stderr));
+    assert_eq!("\x1B[31mred\x1B[0m", "red".red().to_string());
+    assert_eq!("\x1B[1;34mab \x1B[0m", format!("{:3}", "ab".blue().bold()));
+    assert_eq!(
+        "\x1B[31ma\x1B[34mb\x1B[0m",
+        ("a".red() + "b".blue()).to_string()
+    );
+
+    control::unset_override();
+    assert_eq!("red", "red".red().to_string());
+
+    let red = control::with_colors(true, || "red".red().to_string());
+    assert_eq!("\x1B[31mred\x1B[0m", red);
+    assert!(!SHOULD_COLORIZE.should_colorize());
+}
@@ -0,0 +1,30 @@
+//! `control::refresh` reads the environment of the process into the global
+//! state, so it is tested in its own process.
+
+#![cfg(feature = "std")]
+
+extern crate colored;
+
+use colored::control::{self, SHOULD_COLORIZE};
+use colored::Colorize;
+use std::env;
+
+#[test]
+fn refresh_follows_the_environment() {
+    env::remove_var("FORCE_COLOR");
+    env::remove_var("NO_COLOR");
+    env::set_var("CLICOLOR_FORCE", "1");
+    control::refresh();
+    assert!(SHOULD_COLORIZE.should_colorize());
+
+    env::remove_var("CLICOLOR_FORCE");
+    env::set_var("NO_COLOR", "1");
+    assert!(SHOULD_COLORIZE.should_colorize());
+    control::refresh();
+    assert!(!SHOULD_COLORIZE.should_colorize());
+    assert_eq!("red", "red".red().to_string());
+
+    env::set_var("FORCE_COLOR", "3");
+    control::refresh();
+    assert!(SHOULD_COLORIZE.should_colorize());
+}